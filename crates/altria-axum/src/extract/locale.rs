@@ -0,0 +1,116 @@
+//! Locale negotiation from the `Accept-Language` header
+
+use std::convert::Infallible;
+use std::ops::Deref;
+
+use altria::error::parse_accept_language;
+use axum::extract::FromRequestParts;
+use axum::http::{header, request};
+
+/// The locales accepted by the client, most preferred first
+///
+/// Parsed from the `Accept-Language` header with [`parse_accept_language`].
+/// Extraction never fails: a missing or malformed header yields no locales.
+///
+/// # Examples
+///
+/// ```
+/// use altria_axum::extract::locale::AcceptLanguage;
+///
+/// async fn handler(lang: AcceptLanguage) -> String {
+///     lang.first().cloned().unwrap_or_else(|| "en".to_string())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AcceptLanguage(Vec<String>);
+
+impl AcceptLanguage {
+    /// Parse an `Accept-Language` header value
+    #[must_use]
+    pub fn parse(header: &str) -> Self {
+        Self(parse_accept_language(header))
+    }
+
+    /// Get the accepted locales, most preferred first
+    #[must_use]
+    pub fn locales(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Unwrap the accepted locales
+    #[must_use]
+    pub fn into_inner(self) -> Vec<String> {
+        self.0
+    }
+}
+
+impl Deref for AcceptLanguage {
+    type Target = [String];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl IntoIterator for AcceptLanguage {
+    type Item = String;
+    type IntoIter = std::vec::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a AcceptLanguage {
+    type Item = &'a String;
+    type IntoIter = std::slice::Iter<'a, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<S> FromRequestParts<S> for AcceptLanguage
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map(Self::parse)
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    async fn extract(header: Option<&str>) -> AcceptLanguage {
+        let mut request = Request::builder();
+        if let Some(value) = header {
+            request = request.header(header::ACCEPT_LANGUAGE, value);
+        }
+        let (mut parts, ()) = request.body(()).unwrap().into_parts();
+        AcceptLanguage::from_request_parts(&mut parts, &())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_extract() {
+        let lang = extract(Some("en;q=0.5, de-CH, de;q=0.9")).await;
+        assert_eq!(lang.locales(), ["de-CH", "de", "en"]);
+
+        assert!(extract(None).await.is_empty());
+        assert!(extract(Some("*")).await.is_empty());
+    }
+}
@@ -0,0 +1,429 @@
+//! HTTP response rendering for [`altria::error::Error`]
+//!
+//! [`ErrorResponse`] wraps an [`Error`] so it can be returned directly from
+//! axum handlers. Only the public parts of the error (code, message and
+//! context) are rendered; the source chain and backtrace never reach the client.
+//!
+//! [`ProblemResponse`] renders an error as an RFC 9457 `application/problem+json`
+//! document instead.
+//!
+//! Both convert from [`ValidationErrors`], rendering the violations as a
+//! structured `violations` list, and send a `Retry-After` header when the
+//! error carries a [`retry_after`](Error::retry_after) hint.
+//!
+//! Messages are localized for the locales negotiated with the
+//! [`AcceptLanguage`](crate::extract::locale::AcceptLanguage) extractor; see
+//! [`ErrorResponse::with_locales`] and [`ProblemConfig::render_localized`](altria::error::problem::ProblemConfig::render_localized).
+//!
+//! # Examples
+//!
+//! ```
+//! use altria::error;
+//! use altria_axum::response::Result;
+//!
+//! async fn handler() -> Result<&'static str> {
+//!     Err(error!("User not found"; code: 404))?
+//! }
+//! ```
+
+use altria::error::problem::{self, ProblemDetails};
+use altria::error::{Context, Error, ErrorKind, ValidationErrors};
+use axum::Json;
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Convenience type alias for handler results rendered through [`ErrorResponse`]
+pub type Result<T, E = ErrorResponse> = std::result::Result<T, E>;
+
+/// An [`Error`] that can be turned into an HTTP response
+///
+/// The status code is taken from [`Error::http_status`]. The body is a JSON object:
+///
+/// ```json
+/// { "code": 404, "kind": "not_found", "message": "User not found", "context": { "id": "42" } }
+/// ```
+///
+/// `code`, `kind` and `context` are omitted when absent or empty.
+#[derive(Debug)]
+pub struct ErrorResponse(Error);
+
+impl ErrorResponse {
+    /// Wrap an error for rendering
+    #[must_use]
+    pub const fn new(error: Error) -> Self {
+        Self(error)
+    }
+
+    /// Render the message for the preferred locales (builder pattern)
+    ///
+    /// The message is localized with [`Error::localize`], falling back to the
+    /// literal message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use altria_axum::extract::locale::AcceptLanguage;
+    /// use altria_axum::response::{ErrorResponse, Result};
+    ///
+    /// async fn handler(lang: AcceptLanguage) -> Result<&'static str> {
+    ///     let err = Error::new("User 42 not found")
+    ///         .with_code(404)
+    ///         .with_message_key("user.not_found")
+    ///         .with_context_value("id", 42);
+    ///     Err(ErrorResponse::from(err).with_locales(&lang))
+    /// }
+    /// ```
+    #[must_use]
+    pub fn with_locales<L: AsRef<str>>(self, locales: &[L]) -> Self {
+        Self(self.0.localize(locales))
+    }
+
+    /// Get the wrapped error
+    #[must_use]
+    pub const fn error(&self) -> &Error {
+        &self.0
+    }
+
+    /// Unwrap the inner error
+    #[must_use]
+    pub fn into_inner(self) -> Error {
+        self.0
+    }
+
+    /// Get the HTTP status code this error will be rendered with
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        status_for(&self.0)
+    }
+}
+
+impl From<Error> for ErrorResponse {
+    fn from(error: Error) -> Self {
+        Self(error)
+    }
+}
+
+impl From<ValidationErrors> for ErrorResponse {
+    #[track_caller]
+    fn from(errors: ValidationErrors) -> Self {
+        Self(errors.into())
+    }
+}
+
+impl IntoResponse for ErrorResponse {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            code: self.0.code(),
+            kind: self.0.kind(),
+            message: self.0.message(),
+            context: self.0.context(),
+        };
+        let mut response = (self.status(), Json(body)).into_response();
+        if let Some(delay) = self.0.retry_after() {
+            set_retry_after(&mut response, delay);
+        }
+        response
+    }
+}
+
+/// JSON body of an error response
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<ErrorKind>,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Context::is_empty")]
+    context: &'a Context,
+}
+
+/// A [`ProblemDetails`] document rendered as an `application/problem+json` response
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::Error;
+/// use altria::error::problem::ProblemConfig;
+/// use altria_axum::response::ProblemResponse;
+///
+/// async fn handler() -> Result<&'static str, ProblemResponse> {
+///     let config = ProblemConfig::new().with_type_base("https://example.com/problems");
+///     let err = Error::new("Order 7 not found").with_code(404);
+///     Err(config.render(&err).with_instance("/orders/7").into())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ProblemResponse(ProblemDetails);
+
+impl ProblemResponse {
+    /// Wrap a problem details document for rendering
+    #[must_use]
+    pub const fn new(problem: ProblemDetails) -> Self {
+        Self(problem)
+    }
+
+    /// Get the wrapped problem details
+    #[must_use]
+    pub const fn problem(&self) -> &ProblemDetails {
+        &self.0
+    }
+}
+
+impl From<ProblemDetails> for ProblemResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        Self(problem)
+    }
+}
+
+impl From<Error> for ProblemResponse {
+    fn from(error: Error) -> Self {
+        Self(error.to_problem())
+    }
+}
+
+impl From<ValidationErrors> for ProblemResponse {
+    #[track_caller]
+    fn from(errors: ValidationErrors) -> Self {
+        Error::from(errors).into()
+    }
+}
+
+impl IntoResponse for ProblemResponse {
+    fn into_response(self) -> Response {
+        let status =
+            StatusCode::from_u16(self.0.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (
+            status,
+            [(header::CONTENT_TYPE, problem::CONTENT_TYPE)],
+            Json(&self.0),
+        )
+            .into_response();
+        if let Some(delay) = self.0.retry_after() {
+            set_retry_after(&mut response, delay);
+        }
+        response
+    }
+}
+
+/// Set the `Retry-After` header in whole seconds, rounding up
+fn set_retry_after(response: &mut Response, delay: Duration) {
+    let seconds = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+}
+
+/// Map an error to an HTTP status code
+fn status_for(error: &Error) -> StatusCode {
+    StatusCode::from_u16(error.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::locale::AcceptLanguage;
+    use altria::error::problem::ProblemConfig;
+    use altria::error::{Catalog, Violation, set_message_catalog};
+    use axum::body::to_bytes;
+    use serde_json::{Value, json};
+
+    async fn render(error: Error) -> (StatusCode, Value) {
+        let response = ErrorResponse::from(error).into_response();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_status_from_code() {
+        let (status, body) = render(Error::new("not found").with_code(404)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body, json!({ "code": 404, "message": "not found" }));
+    }
+
+    #[tokio::test]
+    async fn test_status_fallback() {
+        let (status, body) = render(Error::new("boom")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body, json!({ "message": "boom" }));
+
+        // Application-specific codes are rendered but don't drive the status
+        let (status, body) = render(Error::new("quota").with_code(10_001)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], 10_001);
+
+        let (status, _) = render(Error::new("ok?").with_code(200)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_status_from_kind() {
+        let error = Error::new("slow down").with_kind(ErrorKind::RateLimited);
+        let (status, body) = render(error).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            body,
+            json!({ "kind": "rate_limited", "message": "slow down" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_rendered() {
+        let error = Error::new("invalid")
+            .with_code(400)
+            .with_context_value("field", "email")
+            .with_context_value("max_length", 254);
+        let (status, body) = render(error).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["context"],
+            json!({ "field": "email", "max_length": 254 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sensitive_context_redacted() {
+        let error = Error::new("unauthorized")
+            .with_code(401)
+            .with_context_value("user", "alice")
+            .with_sensitive_context("token", "eyJhbGciOi");
+        let (_, body) = render(error).await;
+        assert_eq!(
+            body["context"],
+            json!({ "user": "alice", "token": "[REDACTED]" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validation_errors() {
+        let mut errors = ValidationErrors::new();
+        errors.add("email", "invalid_email", "must be a valid email address");
+        errors.push(Violation::new("age", "min", "must be at least 18").with_param("min", 18));
+
+        let response = ErrorResponse::from(errors.clone()).into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["code"], 422);
+        assert_eq!(body["kind"], "invalid_input");
+        assert_eq!(
+            body["context"]["violations"],
+            json!([
+                { "field": "email", "code": "invalid_email", "message": "must be a valid email address" },
+                { "field": "age", "code": "min", "message": "must be at least 18", "params": { "min": 18 } },
+            ])
+        );
+
+        let response = ProblemResponse::from(errors.with_code(400)).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["violations"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_header() {
+        let error = Error::new("slow down")
+            .with_kind(ErrorKind::RateLimited)
+            .with_retry_after(Duration::from_millis(1500));
+
+        let response = ErrorResponse::from(error).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+
+        let error = Error::new("maintenance")
+            .with_code(503)
+            .with_retry_after(Duration::from_secs(120));
+        let response = ProblemResponse::from(error).into_response();
+        assert_eq!(response.headers()[header::RETRY_AFTER], "120");
+
+        let response = ErrorResponse::from(Error::new("boom")).into_response();
+        assert!(!response.headers().contains_key(header::RETRY_AFTER));
+    }
+
+    #[tokio::test]
+    async fn test_localized_message() {
+        // No other test uses message keys, so setting the global catalog is safe
+        set_message_catalog(
+            Catalog::new()
+                .with_message("de", "test.order_closed", "Bestellung {id} ist geschlossen")
+                .with_message("en", "test.order_closed", "Order {id} is closed"),
+        );
+        let error = || {
+            Error::new("order closed")
+                .with_code(409)
+                .with_message_key("test.order_closed")
+                .with_context_value("id", 7)
+        };
+
+        let lang = AcceptLanguage::parse("fr, de-CH;q=0.8, en;q=0.5");
+        let response = ErrorResponse::from(error()).with_locales(&lang);
+        let bytes = to_bytes(response.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["message"], "Bestellung 7 ist geschlossen");
+
+        // Without locales, the literal message is rendered
+        let (_, body) = render(error()).await;
+        assert_eq!(body["message"], "order closed");
+
+        let problem = ProblemConfig::new().render_localized(&error(), &lang);
+        assert_eq!(problem.detail(), "Bestellung 7 ist geschlossen");
+    }
+
+    #[tokio::test]
+    async fn test_source_not_leaked() {
+        let io_err = std::io::Error::other("secret path /etc/shadow");
+        let error = Error::new("read failed")
+            .with_source(io_err)
+            .with_backtrace();
+        let (_, body) = render(error).await;
+        let text = body.to_string();
+        assert!(!text.contains("shadow"));
+        assert_eq!(body, json!({ "message": "read failed" }));
+    }
+
+    #[tokio::test]
+    async fn test_problem_response() {
+        let error = Error::new("stale version")
+            .with_code(409)
+            .with_context_value("version", "3");
+        let response = ProblemResponse::from(error).into_response();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            problem::CONTENT_TYPE
+        );
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "type": "about:blank",
+                "title": "Conflict",
+                "status": 409,
+                "detail": "stale version",
+                "code": 409,
+                "version": "3",
+            })
+        );
+    }
+
+    #[test]
+    fn test_question_mark_conversion() {
+        fn handler() -> Result<()> {
+            Err(Error::new("failed").with_code(409))?
+        }
+
+        let err = handler().unwrap_err();
+        assert_eq!(err.status(), StatusCode::CONFLICT);
+        assert_eq!(err.into_inner().message(), "failed");
+    }
+}
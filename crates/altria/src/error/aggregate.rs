@@ -0,0 +1,375 @@
+//! Aggregation of independent errors
+//!
+//! Batch jobs and fan-out requests can fail in several places at once.
+//! [`ErrorSet`] collects those failures, and [`Error::from_many`] wraps them in a
+//! single [`Error`] whose code and kind come from the most severe child
+//! (see [`ErrorSet::severest`]).
+//!
+//! An aggregate error renders its children in `Display` and [`Report`](super::Report)
+//! output, and [`Error::iter_error_chain`] visits each child and its own chain in turn.
+
+use super::Error;
+use std::error::Error as StdError;
+use std::fmt;
+
+/// A collection of independent errors
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{Error, ErrorSet};
+///
+/// let mut errors = ErrorSet::new();
+/// for id in [1, 2, 3] {
+///     if id != 2 {
+///         errors.push(Error::new(format!("Item {id} failed")).with_code(409));
+///     }
+/// }
+///
+/// let err = errors.into_result().unwrap_err();
+/// assert_eq!(err.code(), Some(409));
+/// assert_eq!(err.to_string(), "[409] 2 errors occurred: [409] Item 1 failed; [409] Item 3 failed");
+/// assert_eq!(err.error_set().unwrap().len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ErrorSet {
+    errors: Vec<Error>,
+}
+
+impl ErrorSet {
+    /// Create an empty set
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { errors: Vec::new() }
+    }
+
+    /// Add an error (builder pattern)
+    #[must_use]
+    pub fn with_error(mut self, error: impl Into<Error>) -> Self {
+        self.push(error);
+        self
+    }
+
+    /// Add an error
+    pub fn push(&mut self, error: impl Into<Error>) {
+        self.errors.push(error.into());
+    }
+
+    /// Get the number of errors
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Check whether the set has no errors
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Get the errors in the order they were added
+    #[must_use]
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// Iterate over the errors in the order they were added
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.errors.iter()
+    }
+
+    /// Get the most severe error
+    ///
+    /// Classified errors, with a code, a kind or an explicit HTTP status,
+    /// outrank plain errors, whose status only defaults to `500`. Among each
+    /// group, higher HTTP statuses outrank lower ones, so server errors (`5xx`)
+    /// outrank client errors (`4xx`). The first error wins ties.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{Error, ErrorKind, ErrorSet};
+    ///
+    /// let errors = ErrorSet::new()
+    ///     .with_error(Error::new("Not found").with_code(404))
+    ///     .with_error(Error::new("Backend down").with_kind(ErrorKind::Unavailable))
+    ///     .with_error(Error::new("Conflict").with_code(409));
+    /// assert_eq!(errors.severest().unwrap().message(), "Backend down");
+    ///
+    /// // A plain error does not outrank a classified one
+    /// let errors = ErrorSet::new()
+    ///     .with_error(Error::new("Something broke"))
+    ///     .with_error(Error::new("Not found").with_code(404));
+    /// assert_eq!(errors.severest().unwrap().message(), "Not found");
+    /// ```
+    #[must_use]
+    pub fn severest(&self) -> Option<&Error> {
+        self.errors.iter().reduce(|severest, error| {
+            if severity(error) > severity(severest) {
+                error
+            } else {
+                severest
+            }
+        })
+    }
+
+    /// Return `Ok(())` if the set is empty, or the errors wrapped in a single [`Error`]
+    ///
+    /// # Errors
+    ///
+    /// Returns the aggregate [`Error`] if at least one error was collected.
+    #[track_caller]
+    pub fn into_result(self) -> Result<(), Error> {
+        self.into_error().map_or(Ok(()), Err)
+    }
+
+    /// Wrap the errors in a single aggregate [`Error`], or `None` if the set is empty
+    ///
+    /// The code, kind and explicit HTTP status of the aggregate are taken from
+    /// the [most severe](Self::severest) error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{Error, ErrorSet};
+    ///
+    /// assert!(ErrorSet::new().into_error().is_none());
+    ///
+    /// let err = ErrorSet::new().with_error("Disk full").into_error().unwrap();
+    /// assert_eq!(err.to_string(), "1 error occurred: Disk full");
+    /// ```
+    #[must_use]
+    #[track_caller]
+    pub fn into_error(self) -> Option<Error> {
+        let message = match self.len() {
+            0 => return None,
+            1 => "1 error occurred".to_string(),
+            n => format!("{n} errors occurred"),
+        };
+        let mut err = Error::new(message);
+        if let Some(severest) = self.severest() {
+            if let Some(code) = severest.code() {
+                err = err.with_code_traced(code);
+            }
+            err.kind = severest.kind();
+            if let Some(status) = severest.extra.as_ref().and_then(|extra| extra.http_status) {
+                err = err.with_http_status(status);
+            }
+        }
+        Some(err.with_source(self))
+    }
+}
+
+/// Rank an error for [`ErrorSet::severest`]: classified first, then by HTTP status
+fn severity(error: &Error) -> (bool, u16) {
+    let classified = error.code.is_some()
+        || error.kind.is_some()
+        || error
+            .extra
+            .as_ref()
+            .is_some_and(|extra| extra.http_status.is_some());
+    (classified, error.http_status())
+}
+
+impl fmt::Display for ErrorSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for ErrorSet {}
+
+impl<E: Into<Error>> Extend<E> for ErrorSet {
+    fn extend<I: IntoIterator<Item = E>>(&mut self, iter: I) {
+        self.errors.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+impl<E: Into<Error>> FromIterator<E> for ErrorSet {
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        let mut errors = Self::new();
+        errors.extend(iter);
+        errors
+    }
+}
+
+impl<'a> IntoIterator for &'a ErrorSet {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+
+impl IntoIterator for ErrorSet {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl Error {
+    /// Wrap several independent errors in a single aggregate error
+    ///
+    /// Returns `None` if there are no errors. The code and kind of the
+    /// aggregate are taken from the most severe child (see
+    /// [`ErrorSet::severest`]); override them with [`with_code`](Self::with_code)
+    /// and [`with_kind`](Self::with_kind) if needed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::from_many([
+    ///     Error::new("Shard 1 timed out").with_code(504),
+    ///     Error::new("Shard 3 rejected the query").with_code(400),
+    /// ])
+    /// .unwrap();
+    ///
+    /// assert_eq!(err.code(), Some(504));
+    /// assert_eq!(err.iter_error_chain().count(), 3); // Aggregate + 2 children
+    ///
+    /// assert!(Error::from_many(Vec::<Error>::new()).is_none());
+    /// ```
+    #[must_use]
+    #[track_caller]
+    pub fn from_many<E: Into<Self>>(errors: impl IntoIterator<Item = E>) -> Option<Self> {
+        errors.into_iter().collect::<ErrorSet>().into_error()
+    }
+
+    /// Get the child errors, if this is an aggregate error
+    #[must_use]
+    pub fn error_set(&self) -> Option<&ErrorSet> {
+        self.source.as_deref()?.downcast_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::io;
+
+    fn aggregate() -> Error {
+        Error::from_many([
+            Error::new("Not found").with_code(404),
+            Error::new("Query failed")
+                .with_kind(ErrorKind::Unavailable)
+                .with_source(io::Error::other("connection reset")),
+            Error::new("Conflict").with_code(409),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_overall_code_and_kind() {
+        let err = aggregate();
+        assert_eq!(err.code(), None);
+        assert_eq!(err.kind(), Some(ErrorKind::Unavailable));
+        assert_eq!(err.http_status(), 503);
+
+        let err = Error::from_many([
+            Error::new("a").with_code(400),
+            Error::new("b").with_code(404),
+            Error::new("c")
+                .with_code(404)
+                .with_kind(ErrorKind::NotFound),
+        ])
+        .unwrap();
+        assert_eq!(err.code(), Some(404));
+        assert_eq!(err.kind(), None); // First of the tied errors wins
+    }
+
+    #[test]
+    fn test_plain_errors_rank_below_classified_ones() {
+        let err = Error::from_many([
+            Error::new("unclassified"),
+            Error::new("bad input").with_code(400),
+            Error::new("also unclassified").with_source(io::Error::other("io")),
+        ])
+        .unwrap();
+        assert_eq!(err.code(), Some(400));
+        assert_eq!(err.http_status(), 400);
+
+        let set: ErrorSet = ["a", "b"].into_iter().collect();
+        assert_eq!(set.severest().unwrap().message(), "a");
+
+        let set = ErrorSet::new()
+            .with_error("plain")
+            .with_error(Error::new("gone").with_http_status(410));
+        assert_eq!(set.severest().unwrap().message(), "gone");
+        assert_eq!(set.into_error().unwrap().http_status(), 410);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(Error::from_many(Vec::<Error>::new()).is_none());
+        assert!(ErrorSet::new().into_error().is_none());
+        assert!(ErrorSet::new().into_result().is_ok());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            aggregate().to_string(),
+            "3 errors occurred: [404] Not found; Query failed; [409] Conflict"
+        );
+        assert_eq!(
+            Error::from_many([Error::new("only")]).unwrap().to_string(),
+            "1 error occurred: only"
+        );
+    }
+
+    #[test]
+    fn test_chain_visits_children() {
+        let err = aggregate();
+        let chain: Vec<String> = err.iter_error_chain().map(ToString::to_string).collect();
+        assert_eq!(
+            chain,
+            [
+                "3 errors occurred: [404] Not found; Query failed; [409] Conflict",
+                "[404] Not found",
+                "Query failed",
+                "connection reset",
+                "[409] Conflict",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_aggregates() {
+        let inner = Error::from_many([Error::new("a"), Error::new("b")]).unwrap();
+        let outer = Error::from_many([inner, Error::new("c")]).unwrap();
+        let chain: Vec<String> = outer
+            .iter_error_chain()
+            .skip(1)
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(chain, ["2 errors occurred: a; b", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_error_set() {
+        let err = aggregate();
+        let set = err.error_set().unwrap();
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.errors()[2].code(), Some(409));
+        assert!(Error::new("plain").error_set().is_none());
+
+        assert!(ErrorSet::new().severest().is_none());
+
+        let set: ErrorSet = ["x", "y"].into_iter().collect();
+        let messages: Vec<&str> = set.iter().map(Error::message).collect();
+        assert_eq!(messages, ["x", "y"]);
+    }
+}
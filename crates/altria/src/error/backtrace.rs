@@ -0,0 +1,167 @@
+//! Global backtrace capture policy for [`Error`]
+//!
+//! Backtraces are expensive, so [`Error`] doesn't capture them by default.
+//! A [`BacktracePolicy`] set once at startup decides when [`Error::new`], the
+//! [`error!`](crate::error!) macro and the `From` conversions capture one
+//! automatically. Individual errors can still opt in or out with
+//! [`Error::with_backtrace`] and [`Error::without_backtrace`].
+
+use super::Error;
+use parking_lot::RwLock;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// When to capture a backtrace automatically
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{set_backtrace_policy, BacktracePolicy, Error};
+///
+/// // Capture backtraces for server errors only
+/// set_backtrace_policy(BacktracePolicy::OnlyForCodes(500..=599));
+///
+/// assert!(Error::new("Not found").with_code_traced(404).backtrace().is_none());
+/// assert!(Error::new("Database down").with_code_traced(503).backtrace().is_some());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum BacktracePolicy {
+    /// Never capture automatically
+    #[default]
+    Never,
+    /// Always capture when an error is created
+    Always,
+    /// Capture when an error code in the range is set
+    ///
+    /// Applies to codes set with [`Error::with_code_traced`], the
+    /// [`error!`](crate::error!) macro and
+    /// [`ResultExt::with_code`](super::ResultExt::with_code), but not to the
+    /// `const` [`Error::with_code`].
+    OnlyForCodes(RangeInclusive<i64>),
+    /// Follow `RUST_LIB_BACKTRACE` / `RUST_BACKTRACE`, like [`Backtrace::capture`]
+    Env,
+}
+
+impl BacktracePolicy {
+    /// Capture a backtrace for a newly created error, if the policy asks for it
+    fn capture_on_create(&self) -> Option<Backtrace> {
+        match self {
+            Self::Always => Some(Backtrace::force_capture()),
+            Self::Env => {
+                let backtrace = Backtrace::capture();
+                (backtrace.status() == BacktraceStatus::Captured).then_some(backtrace)
+            }
+            Self::Never | Self::OnlyForCodes(_) => None,
+        }
+    }
+
+    /// Capture a backtrace when an error code is set, if the policy asks for it
+    fn capture_for_code(&self, code: i64) -> Option<Backtrace> {
+        match self {
+            Self::OnlyForCodes(codes) if codes.contains(&code) => Some(Backtrace::force_capture()),
+            _ => None,
+        }
+    }
+}
+
+/// Process-wide backtrace policy
+static BACKTRACE_POLICY: RwLock<BacktracePolicy> =
+    parking_lot::const_rwlock(BacktracePolicy::Never);
+
+/// Set the process-wide backtrace policy
+///
+/// Typically called once at startup. Errors created before the call are unaffected.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{set_backtrace_policy, BacktracePolicy, Error};
+///
+/// set_backtrace_policy(BacktracePolicy::Always);
+/// assert!(Error::new("Something went wrong").backtrace().is_some());
+///
+/// // Per-error override
+/// assert!(Error::new("Expected").without_backtrace().backtrace().is_none());
+/// ```
+pub fn set_backtrace_policy(policy: BacktracePolicy) {
+    *BACKTRACE_POLICY.write() = policy;
+}
+
+/// Get the process-wide backtrace policy
+#[must_use]
+pub fn backtrace_policy() -> BacktracePolicy {
+    BACKTRACE_POLICY.read().clone()
+}
+
+impl Error {
+    /// Capture a backtrace on creation according to the global policy
+    pub(super) fn apply_backtrace_policy(&mut self) {
+        let captured = BACKTRACE_POLICY.read().capture_on_create();
+        if let Some(backtrace) = captured {
+            self.backtrace = Some(Arc::new(backtrace));
+        }
+    }
+
+    /// Capture a backtrace for a newly set code according to the global policy
+    ///
+    /// Does nothing if the backtrace was already captured or explicitly overridden.
+    pub(super) fn apply_backtrace_policy_for_code(&mut self, code: i64) {
+        if self.backtrace.is_some() || self.backtrace_overridden {
+            return;
+        }
+        let captured = BACKTRACE_POLICY.read().capture_for_code(code);
+        if let Some(backtrace) = captured {
+            self.backtrace = Some(Arc::new(backtrace));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy() {
+        assert_eq!(BacktracePolicy::default(), BacktracePolicy::Never);
+        assert!(BacktracePolicy::Never.capture_on_create().is_none());
+        assert!(BacktracePolicy::Never.capture_for_code(500).is_none());
+    }
+
+    #[test]
+    fn test_always_policy() {
+        let backtrace = BacktracePolicy::Always.capture_on_create().unwrap();
+        assert_eq!(backtrace.status(), BacktraceStatus::Captured);
+        assert!(BacktracePolicy::Always.capture_for_code(500).is_none());
+    }
+
+    #[test]
+    fn test_codes_policy() {
+        let policy = BacktracePolicy::OnlyForCodes(500..=599);
+        assert!(policy.capture_on_create().is_none());
+        assert!(policy.capture_for_code(404).is_none());
+        assert!(policy.capture_for_code(600).is_none());
+        assert!(policy.capture_for_code(500).is_some());
+        assert!(policy.capture_for_code(599).is_some());
+    }
+
+    #[test]
+    fn test_env_policy_matches_std() {
+        let expected = Backtrace::capture().status() == BacktraceStatus::Captured;
+        assert_eq!(BacktracePolicy::Env.capture_on_create().is_some(), expected);
+    }
+
+    #[test]
+    fn test_override() {
+        // Explicit capture still follows the environment
+        let err = Error::new("forced").with_backtrace();
+        assert_eq!(
+            err.backtrace().map(Backtrace::status),
+            Some(Backtrace::capture().status())
+        );
+
+        let mut err = Error::new("suppressed").without_backtrace();
+        err.apply_backtrace_policy_for_code(500);
+        assert!(err.backtrace().is_none());
+    }
+}
@@ -0,0 +1,918 @@
+//! Context storage for [`Error`](super::Error)
+//!
+//! - [`Context`] keeps key-value pairs in insertion order, so rendering is stable
+//! - [`ContextValue`] keeps the type of each value (numbers, booleans, lists, maps)
+//!   instead of stringifying it, so structured log sinks and JSON renderings see
+//!   real data. Plain strings remain the common case and convert implicitly.
+//! - [`ContextOrder`] selects whether `Display` output follows insertion order
+//!   or sorts by key
+//! - Entries can be marked sensitive so their values are redacted when rendered
+//!
+//! With the `serde_json` feature, [`ContextValue`] converts to and from
+//! `serde_json::Value`.
+
+use super::redact::{REDACTED, is_redacted_key};
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Order in which context entries are rendered by `Display`
+///
+/// Serialization always follows insertion order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContextOrder {
+    /// Render entries in the order they were added
+    #[default]
+    Insertion,
+    /// Render entries sorted by key
+    Sorted,
+}
+
+/// Global context rendering order, stored as a `ContextOrder` discriminant
+static CONTEXT_ORDER: AtomicU8 = AtomicU8::new(ContextOrder::Insertion as u8);
+
+/// Set the order in which context entries are rendered by `Display`
+///
+/// This is a process-wide setting, typically configured once at startup.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{set_context_order, ContextOrder, Error};
+///
+/// set_context_order(ContextOrder::Sorted);
+///
+/// let err = Error::new("error")
+///     .with_context_value("b", 2)
+///     .with_context_value("a", 1);
+/// assert_eq!(err.to_string(), "error (a: 1, b: 2)");
+/// ```
+pub fn set_context_order(order: ContextOrder) {
+    CONTEXT_ORDER.store(order as u8, Ordering::Relaxed);
+}
+
+/// Get the order in which context entries are rendered by `Display`
+#[must_use]
+pub fn context_order() -> ContextOrder {
+    if CONTEXT_ORDER.load(Ordering::Relaxed) == ContextOrder::Sorted as u8 {
+        ContextOrder::Sorted
+    } else {
+        ContextOrder::Insertion
+    }
+}
+
+/// Insertion-ordered context key-value pairs
+///
+/// Re-inserting an existing key replaces its value but keeps its position.
+/// Errors typically carry only a handful of entries, so lookups are linear.
+///
+/// Sensitive entries (see [`insert_sensitive`](Self::insert_sensitive) and
+/// [`set_redacted_keys`](super::set_redacted_keys)) render as `[REDACTED]` in
+/// `Display`, `Debug` and serialization; [`get`](Self::get) and
+/// [`iter`](Self::iter) still return the raw values.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::Error;
+///
+/// let err = Error::new("Database error")
+///     .with_context_value("table", "users")
+///     .with_context_value("operation", "insert")
+///     .with_context_value("rows", 3);
+///
+/// let keys: Vec<&str> = err.context().keys().collect();
+/// assert_eq!(keys, ["table", "operation", "rows"]);
+/// assert_eq!(err.to_string(), "Database error (table: users, operation: insert, rows: 3)");
+/// ```
+#[derive(Clone, Default, PartialEq)]
+pub struct Context {
+    entries: Vec<Entry>,
+}
+
+/// A single context entry
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    key: String,
+    value: ContextValue,
+    /// Whether the entry was explicitly marked as sensitive
+    sensitive: bool,
+    /// `Display` form of a non-string value, rendered on first use
+    rendered: Rendered,
+}
+
+/// Lazily rendered string, ignored when comparing entries
+#[derive(Debug, Clone, Default)]
+struct Rendered(OnceLock<String>);
+
+impl PartialEq for Rendered {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Entry {
+    fn pair(&self) -> (&str, &ContextValue) {
+        (&self.key, &self.value)
+    }
+
+    fn into_pair(self) -> (String, ContextValue) {
+        (self.key, self.value)
+    }
+}
+
+impl Context {
+    /// Create an empty context
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Get the number of entries
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the context has no entries
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get a value by key
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&ContextValue> {
+        self.entry(key).map(|entry| &entry.value)
+    }
+
+    /// Get a value by key as a string, rendering non-string values with `Display`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Context;
+    ///
+    /// let mut context = Context::new();
+    /// context.insert("user", "alice");
+    /// context.insert("attempts", 3);
+    ///
+    /// assert_eq!(context.get_str("user"), Some("alice"));
+    /// assert_eq!(context.get_str("attempts"), Some("3"));
+    /// assert_eq!(context.get_str("missing"), None);
+    /// ```
+    #[must_use]
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        let entry = self.entry(key)?;
+        Some(match &entry.value {
+            ContextValue::String(s) => s,
+            value => entry.rendered.0.get_or_init(|| value.to_string()),
+        })
+    }
+
+    /// Find the entry for a key
+    fn entry(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+
+    /// Check whether a key is present
+    #[must_use]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Insert a value, returning the previous value for the key if any
+    ///
+    /// An existing key keeps its original position.
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<ContextValue>,
+    ) -> Option<ContextValue> {
+        let key = key.into();
+        let value = value.into();
+        if let Some(existing) = self.entries.iter_mut().find(|entry| entry.key == key) {
+            existing.rendered = Rendered::default();
+            return Some(std::mem::replace(&mut existing.value, value));
+        }
+        self.entries.push(Entry {
+            key,
+            value,
+            sensitive: false,
+            rendered: Rendered::default(),
+        });
+        None
+    }
+
+    /// Insert a value that is redacted when rendered, returning the previous value if any
+    ///
+    /// The key stays sensitive if it is later re-inserted with [`insert`](Self::insert).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{Context, ContextValue};
+    ///
+    /// let mut context = Context::new();
+    /// context.insert("user", "alice");
+    /// context.insert_sensitive("email", "alice@example.com");
+    ///
+    /// assert!(context.is_sensitive("email"));
+    /// assert_eq!(context.get("email").and_then(ContextValue::as_str), Some("alice@example.com"));
+    /// assert_eq!(format!("{context:?}"), r#"{"user": String("alice"), "email": "[REDACTED]"}"#);
+    /// ```
+    pub fn insert_sensitive(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<ContextValue>,
+    ) -> Option<ContextValue> {
+        let key = key.into();
+        let previous = self.insert(key.clone(), value);
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.key == key) {
+            entry.sensitive = true;
+        }
+        previous
+    }
+
+    /// Check whether the value for a key is redacted when rendered
+    ///
+    /// A key is sensitive if it was inserted with [`insert_sensitive`](Self::insert_sensitive)
+    /// or matches a pattern set with [`set_redacted_keys`](super::set_redacted_keys).
+    #[must_use]
+    pub fn is_sensitive(&self, key: &str) -> bool {
+        self.entry(key).is_some_and(|entry| entry.sensitive) || is_redacted_key(key)
+    }
+
+    /// Remove a value by key, preserving the order of the remaining entries
+    pub fn remove(&mut self, key: &str) -> Option<ContextValue> {
+        let index = self.entries.iter().position(|entry| entry.key == key)?;
+        Some(self.entries.remove(index).value)
+    }
+
+    /// Iterate over entries in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ContextValue)> {
+        self.into_iter()
+    }
+
+    /// Iterate over keys in insertion order
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.key.as_str())
+    }
+
+    /// Iterate over values in insertion order
+    pub fn values(&self) -> impl Iterator<Item = &ContextValue> {
+        self.entries.iter().map(|entry| &entry.value)
+    }
+
+    /// Iterate over entries in insertion order, with sensitive values replaced by `[REDACTED]`
+    pub fn iter_redacted(&self) -> impl Iterator<Item = (&str, Cow<'_, ContextValue>)> {
+        self.iter().map(|(key, value)| {
+            let value = if self.is_sensitive(key) {
+                Cow::Owned(ContextValue::String(REDACTED.to_string()))
+            } else {
+                Cow::Borrowed(value)
+            };
+            (key, value)
+        })
+    }
+
+    /// Collect entries in the given order
+    #[must_use]
+    pub fn ordered(&self, order: ContextOrder) -> Vec<(&str, &ContextValue)> {
+        let mut entries: Vec<_> = self.iter().collect();
+        if order == ContextOrder::Sorted {
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+        }
+        entries
+    }
+
+    /// Collect entries in the given order, with sensitive values redacted
+    pub(super) fn ordered_redacted(
+        &self,
+        order: ContextOrder,
+    ) -> Vec<(&str, Cow<'_, ContextValue>)> {
+        let mut entries: Vec<_> = self.iter_redacted().collect();
+        if order == ContextOrder::Sorted {
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+        }
+        entries
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (key, value) in self.iter() {
+            if self.is_sensitive(key) {
+                map.entry(&key, &REDACTED);
+            } else {
+                map.entry(&key, &value);
+            }
+        }
+        map.finish()
+    }
+}
+
+impl<K: Into<String>, V: Into<ContextValue>> Extend<(K, V)> for Context {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Into<String>, V: Into<ContextValue>> FromIterator<(K, V)> for Context {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut context = Self::new();
+        context.extend(iter);
+        context
+    }
+}
+
+impl<'a> IntoIterator for &'a Context {
+    type Item = (&'a str, &'a ContextValue);
+    type IntoIter = ContextIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ContextIter {
+            inner: self.entries.iter(),
+        }
+    }
+}
+
+impl IntoIterator for Context {
+    type Item = (String, ContextValue);
+    type IntoIter = ContextIntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ContextIntoIter {
+            inner: self.entries.into_iter(),
+        }
+    }
+}
+
+/// Borrowing iterator over context entries in insertion order
+///
+/// Created by iterating over a `&Context`.
+#[derive(Debug, Clone)]
+pub struct ContextIter<'a> {
+    inner: std::slice::Iter<'a, Entry>,
+}
+
+impl<'a> Iterator for ContextIter<'a> {
+    type Item = (&'a str, &'a ContextValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(Entry::pair)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for ContextIter<'_> {}
+
+/// Owning iterator over context entries in insertion order
+///
+/// Created by iterating over a `Context`.
+#[derive(Debug)]
+pub struct ContextIntoIter {
+    inner: std::vec::IntoIter<Entry>,
+}
+
+impl Iterator for ContextIntoIter {
+    type Item = (String, ContextValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(Entry::into_pair)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for ContextIntoIter {}
+
+/// Serializes as a map in insertion order, with sensitive values redacted
+impl Serialize for Context {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (key, value) in self.iter_redacted() {
+            map.serialize_entry(key, &value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Context {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ContextVisitor;
+
+        impl<'de> Visitor<'de> for ContextVisitor {
+            type Value = Context;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of context values")
+            }
+
+            fn visit_map<M>(self, mut map: M) -> Result<Context, M::Error>
+            where
+                M: MapAccess<'de>,
+            {
+                let mut context = Context::new();
+                while let Some((key, value)) = map.next_entry::<String, ContextValue>()? {
+                    context.insert(key, value);
+                }
+                Ok(context)
+            }
+        }
+
+        deserializer.deserialize_map(ContextVisitor)
+    }
+}
+
+/// Renders a context as ` (key: value, ...)`, or nothing when empty
+pub(super) struct ContextDisplay<'a> {
+    pub(super) context: &'a Context,
+    pub(super) order: ContextOrder,
+}
+
+impl fmt::Display for ContextDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.context.is_empty() {
+            return Ok(());
+        }
+
+        write!(f, " (")?;
+        for (i, (key, value)) in self
+            .context
+            .ordered_redacted(self.order)
+            .into_iter()
+            .enumerate()
+        {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{key}: {value}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// A typed value stored in the context of an [`Error`](super::Error)
+///
+/// Serializes untagged, so `ContextValue::Int(3)` becomes `3` and
+/// `ContextValue::String("x")` becomes `"x"` in JSON.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{ContextValue, Error};
+///
+/// let err = Error::new("Upload rejected")
+///     .with_context_value("file", "report.pdf")
+///     .with_context_value("size", 10_485_760)
+///     .with_context_value("compressed", false)
+///     .with_context_value("tags", vec!["q3", "finance"]);
+///
+/// assert_eq!(err.get_context("file"), Some("report.pdf"));
+/// assert_eq!(err.get_context_value("size"), Some(&ContextValue::Int(10_485_760)));
+/// assert_eq!(err.get_context_value("compressed").and_then(ContextValue::as_bool), Some(false));
+/// assert_eq!(err.to_string(), "Upload rejected (file: report.pdf, size: 10485760, compressed: false, tags: [q3, finance])");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ContextValue {
+    /// Absence of a value
+    Null,
+    /// Boolean value
+    Bool(bool),
+    /// Signed integer value
+    Int(i64),
+    /// Floating point value
+    Float(f64),
+    /// String value
+    String(String),
+    /// Ordered list of values
+    List(Vec<Self>),
+    /// Nested map of values, ordered by key
+    Map(BTreeMap<String, Self>),
+}
+
+impl ContextValue {
+    /// Get the value as a string slice, if it is a string
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an `i64`, if it is an integer
+    #[must_use]
+    pub const fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an `f64`, if it is a number
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // Integers are widened on purpose
+    pub const fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Float(f) => Some(*f),
+            Self::Int(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a `bool`, if it is a boolean
+    #[must_use]
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a list, if it is a list
+    #[must_use]
+    pub fn as_list(&self) -> Option<&[Self]> {
+        match self {
+            Self::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a map, if it is a map
+    #[must_use]
+    pub const fn as_map(&self) -> Option<&BTreeMap<String, Self>> {
+        match self {
+            Self::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Check whether the value is [`ContextValue::Null`]
+    #[must_use]
+    pub const fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+}
+
+impl fmt::Display for ContextValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Float(x) => write!(f, "{x}"),
+            Self::String(s) => f.write_str(s),
+            Self::List(list) => {
+                f.write_str("[")?;
+                for (i, value) in list.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_str("]")
+            }
+            Self::Map(map) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{key}: {value}")?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+impl From<String> for ContextValue {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl From<&str> for ContextValue {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<&String> for ContextValue {
+    fn from(s: &String) -> Self {
+        Self::String(s.clone())
+    }
+}
+
+impl From<Cow<'_, str>> for ContextValue {
+    fn from(s: Cow<'_, str>) -> Self {
+        Self::String(s.into_owned())
+    }
+}
+
+impl From<bool> for ContextValue {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($ty:ty),+) => {
+        $(
+            impl From<$ty> for ContextValue {
+                fn from(i: $ty) -> Self {
+                    Self::Int(i64::from(i))
+                }
+            }
+        )+
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, u8, u16, u32);
+
+macro_rules! impl_from_wide_int {
+    ($($ty:ty),+) => {
+        $(
+            impl From<$ty> for ContextValue {
+                /// Values that don't fit in an `i64` are stored as floats
+                #[allow(clippy::cast_precision_loss)]
+                fn from(i: $ty) -> Self {
+                    i64::try_from(i).map_or(Self::Float(i as f64), Self::Int)
+                }
+            }
+        )+
+    };
+}
+
+impl_from_wide_int!(u64, usize, isize);
+
+impl From<f32> for ContextValue {
+    fn from(x: f32) -> Self {
+        Self::Float(f64::from(x))
+    }
+}
+
+impl From<f64> for ContextValue {
+    fn from(x: f64) -> Self {
+        Self::Float(x)
+    }
+}
+
+impl<T: Into<Self>> From<Option<T>> for ContextValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+impl<T: Into<Self>> From<Vec<T>> for ContextValue {
+    fn from(list: Vec<T>) -> Self {
+        Self::List(list.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<Self>> From<BTreeMap<K, V>> for ContextValue {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        Self::Map(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<Self>> From<HashMap<K, V>> for ContextValue {
+    fn from(map: HashMap<K, V>) -> Self {
+        Self::Map(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Value> for ContextValue {
+    fn from(value: serde_json::Value) -> Self {
+        use serde_json::Value;
+
+        match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Bool(b),
+            Value::Number(n) => n
+                .as_i64()
+                .map_or_else(|| Self::Float(n.as_f64().unwrap_or(f64::NAN)), Self::Int),
+            Value::String(s) => Self::String(s),
+            Value::Array(list) => Self::List(list.into_iter().map(Into::into).collect()),
+            Value::Object(map) => Self::Map(map.into_iter().map(|(k, v)| (k, v.into())).collect()),
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl From<ContextValue> for serde_json::Value {
+    fn from(value: ContextValue) -> Self {
+        match value {
+            ContextValue::Null => Self::Null,
+            ContextValue::Bool(b) => Self::Bool(b),
+            ContextValue::Int(i) => Self::from(i),
+            ContextValue::Float(x) => Self::from(x),
+            ContextValue::String(s) => Self::String(s),
+            ContextValue::List(list) => Self::Array(list.into_iter().map(Into::into).collect()),
+            ContextValue::Map(map) => {
+                Self::Object(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_context_insertion_order() {
+        let mut context = Context::new();
+        context.insert("b", 2);
+        context.insert("a", 1);
+        context.insert("c", 3);
+        assert_eq!(context.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+
+        // Replacing keeps the original position
+        assert_eq!(context.insert("b", 20), Some(ContextValue::Int(2)));
+        assert_eq!(context.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(context.get("b"), Some(&ContextValue::Int(20)));
+        assert_eq!(context.len(), 3);
+
+        assert_eq!(context.remove("a"), Some(ContextValue::Int(1)));
+        assert_eq!(context.keys().collect::<Vec<_>>(), ["b", "c"]);
+        assert!(!context.contains_key("a"));
+    }
+
+    #[test]
+    fn test_sensitive_entries() {
+        let mut context = Context::new();
+        context.insert("user", "alice");
+        context.insert_sensitive("token", "s3cr3t");
+
+        // Raw access is unaffected
+        assert_eq!(context.get("token"), Some(&ContextValue::from("s3cr3t")));
+        assert!(context.is_sensitive("token"));
+        assert!(!context.is_sensitive("user"));
+
+        let display = ContextDisplay {
+            context: &context,
+            order: ContextOrder::Insertion,
+        };
+        assert_eq!(display.to_string(), " (user: alice, token: [REDACTED])");
+        assert!(!format!("{context:?}").contains("s3cr3t"));
+        assert_eq!(
+            serde_json::to_string(&context).unwrap(),
+            r#"{"user":"alice","token":"[REDACTED]"}"#
+        );
+
+        // Re-inserting keeps the mark; removing clears it
+        context.insert("token", "other");
+        assert!(context.is_sensitive("token"));
+        context.remove("token");
+        assert!(!context.is_sensitive("token"));
+        context.insert("token", "plain");
+        assert_eq!(display_of(&context), " (user: alice, token: plain)");
+    }
+
+    fn display_of(context: &Context) -> String {
+        ContextDisplay {
+            context,
+            order: ContextOrder::Insertion,
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn test_context_display_orders() {
+        let context: Context = [("zeta", "1"), ("alpha", "2"), ("mid", "3")]
+            .into_iter()
+            .collect();
+
+        let insertion = ContextDisplay {
+            context: &context,
+            order: ContextOrder::Insertion,
+        };
+        assert_eq!(insertion.to_string(), " (zeta: 1, alpha: 2, mid: 3)");
+
+        let sorted = ContextDisplay {
+            context: &context,
+            order: ContextOrder::Sorted,
+        };
+        assert_eq!(sorted.to_string(), " (alpha: 2, mid: 3, zeta: 1)");
+
+        let empty = ContextDisplay {
+            context: &Context::new(),
+            order: ContextOrder::Sorted,
+        };
+        assert_eq!(empty.to_string(), "");
+    }
+
+    #[test]
+    fn test_context_serde_preserves_order() {
+        let context: Context = [("z", 1), ("a", 2), ("m", 3)].into_iter().collect();
+        let json = serde_json::to_string(&context).unwrap();
+        assert_eq!(json, r#"{"z":1,"a":2,"m":3}"#);
+
+        let restored: Context = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, context);
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(ContextValue::from("a"), ContextValue::String("a".into()));
+        assert_eq!(ContextValue::from(7_u8), ContextValue::Int(7));
+        assert_eq!(ContextValue::from(-7_i64), ContextValue::Int(-7));
+        assert_eq!(ContextValue::from(7_usize), ContextValue::Int(7));
+        assert_eq!(
+            ContextValue::from(u64::MAX),
+            ContextValue::Float(18_446_744_073_709_551_615.0)
+        );
+        assert_eq!(ContextValue::from(1.5), ContextValue::Float(1.5));
+        assert_eq!(ContextValue::from(true), ContextValue::Bool(true));
+        assert_eq!(ContextValue::from(None::<i32>), ContextValue::Null);
+        assert_eq!(ContextValue::from(Some(2)), ContextValue::Int(2));
+        assert_eq!(
+            ContextValue::from(vec![1, 2]),
+            ContextValue::List(vec![ContextValue::Int(1), ContextValue::Int(2)])
+        );
+    }
+
+    #[test]
+    fn test_accessors() {
+        let value = ContextValue::from(3);
+        assert_eq!(value.as_i64(), Some(3));
+        assert_eq!(value.as_f64(), Some(3.0));
+        assert_eq!(value.as_str(), None);
+        assert!(!value.is_null());
+
+        let value = ContextValue::from(vec!["a"]);
+        assert_eq!(value.as_list().map(<[_]>::len), Some(1));
+
+        let value = ContextValue::from(HashMap::from([("k", 1)]));
+        assert_eq!(value.as_map().unwrap()["k"], ContextValue::Int(1));
+    }
+
+    #[test]
+    fn test_display() {
+        let value = ContextValue::from(BTreeMap::from([
+            ("ids", ContextValue::from(vec![1, 2])),
+            ("name", ContextValue::from("x")),
+            ("ok", ContextValue::from(true)),
+            ("none", ContextValue::Null),
+        ]));
+        assert_eq!(
+            value.to_string(),
+            "{ids: [1, 2], name: x, none: null, ok: true}"
+        );
+        assert_eq!(ContextValue::from(0.25).to_string(), "0.25");
+    }
+
+    #[test]
+    fn test_serde_untagged() {
+        let value = ContextValue::from(BTreeMap::from([
+            ("count", ContextValue::from(3)),
+            ("ratio", ContextValue::from(0.5)),
+            ("tags", ContextValue::from(vec!["a", "b"])),
+            ("missing", ContextValue::Null),
+        ]));
+        let json = serde_json::to_value(&value).unwrap();
+        assert_eq!(
+            json,
+            json!({ "count": 3, "ratio": 0.5, "tags": ["a", "b"], "missing": null })
+        );
+
+        let restored: ContextValue = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, value);
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_serde_json_value_conversion() {
+        let json = json!({ "a": [1, 2.5, "x", null, true] });
+        let value = ContextValue::from(json.clone());
+        assert_eq!(value.as_map().unwrap()["a"].as_list().unwrap().len(), 5);
+        assert_eq!(serde_json::Value::from(value), json);
+    }
+}
@@ -0,0 +1,200 @@
+//! Downcasting helpers across the source chain of [`Error`]
+//!
+//! These helpers search the sources of an error, in the order of
+//! [`Error::iter_error_chain`], for a concrete error type. The error itself is
+//! never matched, so searching for [`Error`] finds nested errors only.
+
+use super::{Error, ErrorSet};
+use std::error::Error as StdError;
+
+impl Error {
+    /// Find the first source of type `E` in the chain
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use std::io;
+    ///
+    /// let err = Error::new("Failed to load settings")
+    ///     .with_source(Error::new("Failed to read config")
+    ///         .with_source(io::Error::from(io::ErrorKind::NotFound)));
+    ///
+    /// let io_err = err.find_source::<io::Error>().unwrap();
+    /// assert_eq!(io_err.kind(), io::ErrorKind::NotFound);
+    ///
+    /// let inner = err.find_source::<Error>().unwrap();
+    /// assert_eq!(inner.message(), "Failed to read config");
+    /// ```
+    #[must_use]
+    pub fn find_source<E: StdError + 'static>(&self) -> Option<&E> {
+        self.find_sources().next()
+    }
+
+    /// Iterate over every source of type `E` in the chain
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("Request failed")
+    ///     .with_source(Error::new("Query failed")
+    ///         .with_source(Error::new("Connection lost")));
+    ///
+    /// let messages: Vec<&str> = err.find_sources::<Error>().map(Error::message).collect();
+    /// assert_eq!(messages, ["Query failed", "Connection lost"]);
+    /// ```
+    pub fn find_sources<E: StdError + 'static>(&self) -> impl Iterator<Item = &E> {
+        self.iter_error_chain()
+            .skip(1)
+            .filter_map(<dyn StdError>::downcast_ref)
+    }
+
+    /// Check whether the chain contains a source of type `E`
+    ///
+    /// Unlike [`<dyn Error>::is`](StdError#method.is), this error itself is not
+    /// checked, so `is::<Error>()` is only true for nested errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use std::fmt;
+    /// use std::io;
+    ///
+    /// let err = Error::new("Upload failed").with_source(io::Error::other("disk full"));
+    ///
+    /// assert!(err.is::<io::Error>());
+    /// assert!(!err.is::<fmt::Error>());
+    /// assert!(!err.is::<Error>());
+    /// ```
+    #[must_use]
+    pub fn is<E: StdError + 'static>(&self) -> bool {
+        self.find_source::<E>().is_some()
+    }
+
+    /// Get the deepest error in the source chain
+    ///
+    /// Follows [`source`](StdError::source) links and returns the last error,
+    /// or this error if it has no source. The chain stops at aggregate errors
+    /// (see [`Error::from_many`]), whose children are independent causes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use std::io;
+    ///
+    /// let err = Error::new("Failed to load settings")
+    ///     .with_source(Error::new("Failed to read config")
+    ///         .with_source(io::Error::other("permission denied")));
+    /// assert_eq!(err.root_cause().to_string(), "permission denied");
+    ///
+    /// let err = Error::new("No source");
+    /// assert_eq!(err.root_cause().to_string(), "No source");
+    /// ```
+    #[must_use]
+    pub fn root_cause(&self) -> &(dyn StdError + 'static) {
+        let mut current: &(dyn StdError + 'static) = self;
+        while let Some(source) = current.source() {
+            if source.is::<ErrorSet>() {
+                break;
+            }
+            current = source;
+        }
+        current
+    }
+
+    /// Downcast the root cause to a concrete type
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use std::io;
+    ///
+    /// let err = Error::new("Request failed")
+    ///     .with_source(io::Error::from(io::ErrorKind::TimedOut));
+    ///
+    /// let root = err.downcast_root_cause::<io::Error>().unwrap();
+    /// assert_eq!(root.kind(), io::ErrorKind::TimedOut);
+    /// ```
+    #[must_use]
+    pub fn downcast_root_cause<E: StdError + 'static>(&self) -> Option<&E> {
+        self.root_cause().downcast_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+    use std::io;
+
+    fn nested() -> Error {
+        Error::new("outer").with_source(
+            Error::new("middle")
+                .with_code(503)
+                .with_source(io::Error::new(io::ErrorKind::NotFound, "missing")),
+        )
+    }
+
+    #[test]
+    fn test_find_source() {
+        let err = nested();
+        assert_eq!(err.find_source::<Error>().unwrap().code(), Some(503));
+        assert_eq!(
+            err.find_source::<io::Error>().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(err.find_source::<fmt::Error>().is_none());
+
+        // The error itself is never matched
+        assert!(Error::new("alone").find_source::<Error>().is_none());
+        assert!(!Error::new("alone").is::<Error>());
+        assert!(err.is::<Error>());
+    }
+
+    #[test]
+    fn test_find_sources_in_order() {
+        let err = Error::new("a").with_source(nested());
+        let messages: Vec<&str> = err.find_sources::<Error>().map(Error::message).collect();
+        assert_eq!(messages, ["outer", "middle"]);
+    }
+
+    #[test]
+    fn test_root_cause() {
+        let err = nested();
+        assert_eq!(err.root_cause().to_string(), "missing");
+        assert_eq!(
+            err.downcast_root_cause::<io::Error>().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(err.downcast_root_cause::<Error>().is_none());
+
+        let err = Error::new("alone");
+        let root = err.downcast_root_cause::<Error>().unwrap();
+        assert_eq!(root.message(), "alone");
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let err = Error::new("batch failed").with_source(
+            Error::from_many([
+                Error::new("first"),
+                Error::new("second").with_source(io::Error::other("io")),
+            ])
+            .unwrap(),
+        );
+
+        // The aggregate is the root cause, but its children are still searched
+        assert_eq!(
+            err.root_cause().to_string(),
+            "2 errors occurred: first; second"
+        );
+        assert!(err.is::<io::Error>());
+        let messages: Vec<&str> = err.find_sources::<Error>().map(Error::message).collect();
+        assert_eq!(messages, ["2 errors occurred", "first", "second"]);
+    }
+}
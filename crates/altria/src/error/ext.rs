@@ -0,0 +1,395 @@
+//! Extension traits for attaching error information to `Result` and `Option`
+//!
+//! These traits replace the common `map_err(|e| Error::new(..).with_source(e))`
+//! pattern:
+//! - [`ResultExt`] converts any `Result<T, E: std::error::Error>` into [`Result<T>`],
+//!   preserving the original error as the source
+//! - [`OptionExt`] turns `None` into an [`Error`]
+//!
+//! # Examples
+//!
+//! ```
+//! use altria::error::{OptionExt, Result, ResultExt};
+//! use std::collections::HashMap;
+//!
+//! fn load(path: &str) -> Result<String> {
+//!     std::fs::read_to_string(path)
+//!         .context("loading config")
+//!         .with_code(500)
+//!         .with_context_value("path", path)
+//! }
+//!
+//! fn lookup(users: &HashMap<u64, String>, id: u64) -> Result<&String> {
+//!     users
+//!         .get(&id)
+//!         .with_context(|| format!("user {id} not found"))
+//!         .with_code(404)
+//! }
+//!
+//! let err = load("/nonexistent/config.toml").unwrap_err();
+//! assert_eq!(err.message(), "loading config");
+//! assert_eq!(err.code(), Some(500));
+//!
+//! let err = lookup(&HashMap::new(), 7).unwrap_err();
+//! assert_eq!(err.to_string(), "[404] user 7 not found");
+//! ```
+
+use super::{ContextValue, Error, ErrorKind, Result};
+use std::error::Error as StdError;
+use std::io;
+
+/// Extension methods for `Result<T, E>` where `E` is any standard error
+///
+/// If `E` is already an [`Error`], [`with_code`](Self::with_code) and the
+/// `with_context_value*` methods update it in place. Any other error is first
+/// converted into an [`Error`] whose message is the error's `Display` output
+/// and whose source is the original error.
+///
+/// [`context`](Self::context) and [`with_context`](Self::with_context) always
+/// add a new error on top, keeping the previous one as the source.
+///
+/// Either way, the kind of an [`io::Error`] is mapped with
+/// [`ErrorKind::from_io`], as with `From<io::Error>`.
+pub trait ResultExt<T> {
+    /// Wrap the error with a message
+    ///
+    /// # Errors
+    ///
+    /// Returns the converted error if `self` is `Err`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::ResultExt;
+    /// use std::error::Error as StdError;
+    ///
+    /// let err = "abc".parse::<u32>().context("parsing port").unwrap_err();
+    /// assert_eq!(err.message(), "parsing port");
+    /// assert!(err.source().is_some());
+    /// ```
+    fn context(self, message: impl Into<String>) -> Result<T>;
+
+    /// Wrap the error with a lazily evaluated message
+    ///
+    /// The closure is only called if the result is an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns the converted error if `self` is `Err`.
+    fn with_context<M, F>(self, f: F) -> Result<T>
+    where
+        M: Into<String>,
+        F: FnOnce() -> M;
+
+    /// Set the error code
+    ///
+    /// # Errors
+    ///
+    /// Returns the converted error if `self` is `Err`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::ResultExt;
+    ///
+    /// let err = "abc".parse::<u32>().with_code(400).unwrap_err();
+    /// assert_eq!(err.code(), Some(400));
+    /// assert_eq!(err.message(), "invalid digit found in string");
+    /// ```
+    fn with_code(self, code: i64) -> Result<T>;
+
+    /// Add a context key-value pair
+    ///
+    /// # Errors
+    ///
+    /// Returns the converted error if `self` is `Err`.
+    fn with_context_value(
+        self,
+        key: impl Into<String>,
+        value: impl Into<ContextValue>,
+    ) -> Result<T>;
+
+    /// Add a context key-value pair with a lazily evaluated value
+    ///
+    /// The closure is only called if the result is an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns the converted error if `self` is `Err`.
+    fn with_context_value_lazy<V, F>(self, key: impl Into<String>, f: F) -> Result<T>
+    where
+        V: Into<ContextValue>,
+        F: FnOnce() -> V;
+}
+
+impl<T, E> ResultExt<T> for std::result::Result<T, E>
+where
+    E: StdError + Send + Sync + 'static,
+{
+    #[track_caller]
+    fn context(self, message: impl Into<String>) -> Result<T> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(wrap(message.into(), Box::new(e))),
+        }
+    }
+
+    #[track_caller]
+    fn with_context<M, F>(self, f: F) -> Result<T>
+    where
+        M: Into<String>,
+        F: FnOnce() -> M,
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(wrap(f().into(), Box::new(e))),
+        }
+    }
+
+    #[track_caller]
+    fn with_code(self, code: i64) -> Result<T> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(into_error(e).with_code_traced(code)),
+        }
+    }
+
+    #[track_caller]
+    fn with_context_value(
+        self,
+        key: impl Into<String>,
+        value: impl Into<ContextValue>,
+    ) -> Result<T> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(into_error(e).with_context_value(key, value)),
+        }
+    }
+
+    #[track_caller]
+    fn with_context_value_lazy<V, F>(self, key: impl Into<String>, f: F) -> Result<T>
+    where
+        V: Into<ContextValue>,
+        F: FnOnce() -> V,
+    {
+        match self {
+            Ok(value) => Ok(value),
+            Err(e) => Err(into_error(e).with_context_value(key, f())),
+        }
+    }
+}
+
+/// Extension methods for turning `None` into an [`Error`]
+///
+/// Chain [`ResultExt`] methods afterwards to add a code or context values.
+pub trait OptionExt<T> {
+    /// Convert `None` into an error with the given message
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `self` is `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{OptionExt, ResultExt};
+    ///
+    /// let value: Option<u32> = None;
+    /// let err = value.context("missing value").with_code(404).unwrap_err();
+    /// assert_eq!(err.message(), "missing value");
+    /// assert_eq!(err.code(), Some(404));
+    /// ```
+    fn context(self, message: impl Into<String>) -> Result<T>;
+
+    /// Convert `None` into an error with a lazily evaluated message
+    ///
+    /// The closure is only called if the option is `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `self` is `None`.
+    fn with_context<M, F>(self, f: F) -> Result<T>
+    where
+        M: Into<String>,
+        F: FnOnce() -> M;
+}
+
+// Closures would hide the caller's location from `#[track_caller]`
+#[allow(clippy::option_if_let_else)]
+impl<T> OptionExt<T> for Option<T> {
+    #[track_caller]
+    fn context(self, message: impl Into<String>) -> Result<T> {
+        match self {
+            Some(value) => Ok(value),
+            None => Err(Error::new(message)),
+        }
+    }
+
+    #[track_caller]
+    fn with_context<M, F>(self, f: F) -> Result<T>
+    where
+        M: Into<String>,
+        F: FnOnce() -> M,
+    {
+        match self {
+            Some(value) => Ok(value),
+            None => Err(Error::new(f())),
+        }
+    }
+}
+
+/// Convert any standard error into an [`Error`]
+///
+/// An [`Error`] is returned unchanged; anything else is [wrapped](wrap) with
+/// its `Display` output as the message.
+#[track_caller]
+fn into_error<E>(error: E) -> Error
+where
+    E: StdError + Send + Sync + 'static,
+{
+    let boxed: Box<dyn StdError + Send + Sync> = Box::new(error);
+    match boxed.downcast::<Error>() {
+        Ok(err) => *err,
+        Err(source) => wrap(source.to_string(), source),
+    }
+}
+
+/// Create an [`Error`] with a message and a source
+///
+/// The kind of an [`io::Error`] source is mapped with [`ErrorKind::from_io`].
+#[track_caller]
+pub(super) fn wrap(message: String, source: Box<dyn StdError + Send + Sync>) -> Error {
+    let mut err = Error::new(message);
+    err.kind = source
+        .downcast_ref::<io::Error>()
+        .and_then(|e| ErrorKind::from_io(e.kind()));
+    err.source = Some(source.into());
+    err
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn io_failure() -> std::result::Result<(), io::Error> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "config.toml"))
+    }
+
+    #[test]
+    fn test_context_preserves_source() {
+        let err = io_failure().context("loading config").unwrap_err();
+        assert_eq!(err.message(), "loading config");
+        assert_eq!(err.code(), None);
+
+        let source = err.source().unwrap();
+        let io_err = source.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io_err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_context_maps_io_kind() {
+        let err = io_failure().context("loading config").unwrap_err();
+        assert!(err.is_kind(ErrorKind::NotFound));
+
+        let err = io_failure().with_context(|| "loading config").unwrap_err();
+        assert!(err.is_kind(ErrorKind::NotFound));
+
+        let err: Error = io::Error::new(io::ErrorKind::NotFound, "gone").into();
+        assert!(err.is_kind(ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn test_context_on_error_adds_layer() {
+        let result: Result<()> = Err(Error::new("inner").with_code(500));
+        let err = result.context("outer").unwrap_err();
+        assert_eq!(err.message(), "outer");
+        assert_eq!(err.iter_error_chain().count(), 2);
+    }
+
+    #[test]
+    fn test_with_code_on_foreign_error() {
+        let err = io_failure().with_code(404).unwrap_err();
+        assert_eq!(err.code(), Some(404));
+        assert_eq!(err.message(), "config.toml");
+        assert!(err.is_kind(ErrorKind::NotFound));
+        assert!(err.source().unwrap().is::<io::Error>());
+    }
+
+    #[test]
+    fn test_with_code_on_error_updates_in_place() {
+        let err = io_failure()
+            .context("loading config")
+            .with_code(500)
+            .with_context_value("path", "/etc/app")
+            .unwrap_err();
+
+        assert_eq!(err.message(), "loading config");
+        assert_eq!(err.code(), Some(500));
+        assert_eq!(err.get_context("path"), Some("/etc/app"));
+        assert_eq!(err.iter_error_chain().count(), 2);
+    }
+
+    #[test]
+    fn test_lazy_variants() {
+        let calls = Cell::new(0);
+        let ok: std::result::Result<u8, io::Error> = Ok(1);
+        let value = ok
+            .with_context(|| {
+                calls.set(calls.get() + 1);
+                "never"
+            })
+            .with_context_value_lazy("key", || {
+                calls.set(calls.get() + 1);
+                "never"
+            })
+            .unwrap();
+        assert_eq!(value, 1);
+        assert_eq!(calls.get(), 0);
+
+        let err = io_failure()
+            .with_context(|| format!("reading {}", "config"))
+            .with_context_value_lazy("attempt", || 3)
+            .unwrap_err();
+        assert_eq!(err.message(), "reading config");
+        assert_eq!(
+            err.get_context_value("attempt"),
+            Some(&ContextValue::Int(3))
+        );
+    }
+
+    #[test]
+    fn test_location_is_call_site() {
+        let line = line!() + 1;
+        let err = io_failure().context("loading config").unwrap_err();
+        assert_eq!(err.location().unwrap().file(), file!());
+        assert_eq!(err.location().unwrap().line(), line);
+
+        let line = line!() + 1;
+        let err = io_failure().with_code(404).unwrap_err();
+        assert_eq!(err.location().unwrap().line(), line);
+
+        let line = line!() + 1;
+        let err = None::<u8>.context("missing").unwrap_err();
+        assert_eq!(err.location().unwrap().line(), line);
+    }
+
+    #[test]
+    fn test_option_ext() {
+        let some = Some(5);
+        assert_eq!(some.context("missing").unwrap(), 5);
+
+        let none: Option<u8> = None;
+        let err = none.context("missing").unwrap_err();
+        assert_eq!(err.message(), "missing");
+        assert!(err.source().is_none());
+
+        let none: Option<u8> = None;
+        let err = none
+            .with_context(|| "lazy missing")
+            .with_code(404)
+            .unwrap_err();
+        assert_eq!(err.message(), "lazy missing");
+        assert_eq!(err.code(), Some(404));
+    }
+}
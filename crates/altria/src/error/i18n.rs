@@ -0,0 +1,553 @@
+//! Localized error messages
+//!
+//! An error can carry a message key ([`Error::with_message_key`]) next to its
+//! literal message, and its context entries double as the named arguments of the
+//! localized message. A [`MessageCatalog`] renders a key for a locale, and
+//! [`Error::localized_message`] tries the preferred locales in order, falling
+//! back to the literal message when none of them has a translation.
+//!
+//! [`Catalog`] is a simple in-memory catalog of message patterns per locale.
+//! With the `toml` feature, it can be loaded from TOML bundles on disk.
+
+use super::{Context, Error, REDACTED};
+use parking_lot::RwLock;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+/// A source of localized message patterns
+///
+/// Implement this trait to plug in another localization system, such as Fluent.
+pub trait MessageCatalog: Send + Sync {
+    /// Render the message for a key in a locale
+    ///
+    /// `args` holds the named arguments of the message. Returns `None` if the
+    /// key has no translation in exactly this locale; fallback to less specific
+    /// locales is handled by the caller.
+    fn message(&self, locale: &str, key: &str, args: &Context) -> Option<String>;
+}
+
+/// Process-wide message catalog
+static MESSAGE_CATALOG: RwLock<Option<Arc<dyn MessageCatalog>>> = parking_lot::const_rwlock(None);
+
+/// Set the process-wide message catalog used by [`Error::localized_message`]
+///
+/// Replaces any previously set catalog; typically called once at startup.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{set_message_catalog, Catalog, Error};
+///
+/// set_message_catalog(
+///     Catalog::new().with_message("de", "user.not_found", "Benutzer {id} nicht gefunden"),
+/// );
+///
+/// let err = Error::new("User 42 not found")
+///     .with_message_key("user.not_found")
+///     .with_context_value("id", 42);
+/// assert_eq!(err.localized_message(&["de-AT"]), "Benutzer 42 nicht gefunden");
+/// ```
+pub fn set_message_catalog(catalog: impl MessageCatalog + 'static) {
+    *MESSAGE_CATALOG.write() = Some(Arc::new(catalog));
+}
+
+/// Get the process-wide message catalog, if one was set
+#[must_use]
+pub fn message_catalog() -> Option<Arc<dyn MessageCatalog>> {
+    MESSAGE_CATALOG.read().clone()
+}
+
+/// Parse an `Accept-Language` header value into locales, most preferred first
+///
+/// Locales with a quality of zero and the `*` wildcard are dropped. Locales with
+/// equal quality keep their header order.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::parse_accept_language;
+///
+/// assert_eq!(
+///     parse_accept_language("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5"),
+///     ["fr-CH", "fr", "en", "de"]
+/// );
+/// ```
+#[must_use]
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut locales: Vec<(&str, f32)> = header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let locale = parts.next().filter(|l| !l.is_empty() && *l != "*")?;
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+            (quality > 0.0).then_some((locale, quality))
+        })
+        .collect();
+    // Stable sort keeps the header order for equal qualities
+    locales.sort_by(|a, b| b.1.total_cmp(&a.1));
+    locales
+        .into_iter()
+        .map(|(locale, _)| locale.to_string())
+        .collect()
+}
+
+/// An in-memory catalog of message patterns per locale
+///
+/// Patterns reference arguments by name in braces, such as `{id}`; use `{{` and
+/// `}}` for literal braces. Arguments missing from the context are left as is,
+/// and sensitive arguments are rendered as [`REDACTED`].
+///
+/// Locales are matched case-insensitively, and `_` is treated as `-`.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{Catalog, Error};
+///
+/// let catalog = Catalog::new()
+///     .with_message("en", "order.too_large", "Orders are limited to {max} items")
+///     .with_message("fr", "order.too_large", "Les commandes sont limitées à {max} articles");
+///
+/// let err = Error::new("Order has 120 items")
+///     .with_message_key("order.too_large")
+///     .with_context_value("max", 100);
+///
+/// assert_eq!(
+///     err.localized_message_with(&catalog, &["fr-FR", "en"]),
+///     "Les commandes sont limitées à 100 articles"
+/// );
+/// assert_eq!(err.localized_message_with(&catalog, &["ja"]), "Order has 120 items");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    /// Message patterns by normalized locale, then by key
+    bundles: HashMap<String, HashMap<String, String>>,
+}
+
+impl Catalog {
+    /// Create an empty catalog
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a message pattern (builder pattern)
+    #[must_use]
+    pub fn with_message(
+        mut self,
+        locale: &str,
+        key: impl Into<String>,
+        pattern: impl Into<String>,
+    ) -> Self {
+        self.insert(locale, key, pattern);
+        self
+    }
+
+    /// Add a message pattern, replacing any previous pattern for the key and locale
+    pub fn insert(&mut self, locale: &str, key: impl Into<String>, pattern: impl Into<String>) {
+        self.bundles
+            .entry(normalize_locale(locale))
+            .or_default()
+            .insert(key.into(), pattern.into());
+    }
+
+    /// Get the normalized locales that have at least one message, sorted
+    #[must_use]
+    pub fn locales(&self) -> Vec<&str> {
+        let mut locales: Vec<&str> = self.bundles.keys().map(String::as_str).collect();
+        locales.sort_unstable();
+        locales
+    }
+
+    /// Get the raw pattern for a key in a locale
+    #[must_use]
+    pub fn pattern(&self, locale: &str, key: &str) -> Option<&str> {
+        self.bundles
+            .get(&normalize_locale(locale))?
+            .get(key)
+            .map(String::as_str)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl Catalog {
+    /// Add the messages of a TOML bundle for a locale
+    ///
+    /// Nested tables produce dotted keys, so `not_found` in a `[user]` table
+    /// becomes `user.not_found`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Catalog;
+    ///
+    /// let mut catalog = Catalog::new();
+    /// catalog.add_toml("en", r#"
+    ///     internal = "Something went wrong"
+    ///
+    ///     [user]
+    ///     not_found = "User {id} not found"
+    /// "#).unwrap();
+    ///
+    /// assert_eq!(catalog.pattern("en", "user.not_found"), Some("User {id} not found"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bundle is not valid TOML or contains a value
+    /// that is neither a string nor a table.
+    pub fn add_toml(&mut self, locale: &str, source: &str) -> super::Result<()> {
+        use super::ResultExt;
+
+        let table = source
+            .parse::<toml::Table>()
+            .context("Invalid message bundle")
+            .with_context_value("locale", locale)?;
+        self.add_toml_table(locale, "", table)
+    }
+
+    /// Load a catalog from a directory of TOML bundles
+    ///
+    /// Each `<locale>.toml` file in the directory, such as `en.toml` or
+    /// `pt-BR.toml`, holds the messages of one locale. Other files are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory or a bundle cannot be read, or a bundle
+    /// is invalid (see [`add_toml`](Self::add_toml)).
+    pub fn from_dir(path: impl AsRef<std::path::Path>) -> super::Result<Self> {
+        use super::ResultExt;
+
+        let path = path.as_ref();
+        let mut catalog = Self::new();
+        let entries = std::fs::read_dir(path)
+            .context("Failed to read message catalog directory")
+            .with_context_value_lazy("path", || path.display().to_string())?;
+        for entry in entries {
+            let file = entry
+                .context("Failed to read message catalog directory")?
+                .path();
+            if file.extension().is_none_or(|ext| ext != "toml") {
+                continue;
+            }
+            let Some(locale) = file.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let source = std::fs::read_to_string(&file)
+                .context("Failed to read message bundle")
+                .with_context_value_lazy("path", || file.display().to_string())?;
+            catalog.add_toml(locale, &source)?;
+        }
+        Ok(catalog)
+    }
+
+    /// Add the entries of a TOML table, prefixing keys with the enclosing tables
+    fn add_toml_table(
+        &mut self,
+        locale: &str,
+        prefix: &str,
+        table: toml::Table,
+    ) -> super::Result<()> {
+        for (name, value) in table {
+            let key = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}.{name}")
+            };
+            match value {
+                toml::Value::String(pattern) => self.insert(locale, key, pattern),
+                toml::Value::Table(table) => self.add_toml_table(locale, &key, table)?,
+                other => {
+                    return Err(Error::new("Message patterns must be strings")
+                        .with_context_value("locale", locale)
+                        .with_context_value("key", key)
+                        .with_context_value("type", other.type_str()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MessageCatalog for Catalog {
+    fn message(&self, locale: &str, key: &str, args: &Context) -> Option<String> {
+        self.pattern(locale, key)
+            .map(|pattern| interpolate(pattern, args))
+    }
+}
+
+impl Error {
+    /// Set the catalog key of the localized message (builder pattern)
+    ///
+    /// The context entries are the named arguments of the message. The literal
+    /// message is kept as the fallback; see [`localized_message`](Self::localized_message).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("User 42 not found")
+    ///     .with_message_key("user.not_found")
+    ///     .with_context_value("id", 42);
+    /// assert_eq!(err.message_key(), Some("user.not_found"));
+    /// ```
+    #[must_use]
+    pub fn with_message_key(mut self, key: impl Into<String>) -> Self {
+        self.extra_mut().message_key = Some(key.into());
+        self
+    }
+
+    /// Get the catalog key of the localized message, if set
+    #[must_use]
+    pub fn message_key(&self) -> Option<&str> {
+        self.extra.as_ref()?.message_key.as_deref()
+    }
+
+    /// Render the message for the preferred locales with the process-wide catalog
+    ///
+    /// Falls back to the literal message if no catalog is set; see
+    /// [`localized_message_with`](Self::localized_message_with) for the lookup rules.
+    #[must_use]
+    pub fn localized_message<L: AsRef<str>>(&self, locales: &[L]) -> Cow<'_, str> {
+        message_catalog().map_or(Cow::Borrowed(&self.message), |catalog| {
+            self.localized_message_with(catalog.as_ref(), locales)
+        })
+    }
+
+    /// Replace the message with its localization for the preferred locales (builder pattern)
+    ///
+    /// Useful right before rendering an error for a client. The message key is
+    /// kept, and the message is left unchanged if no translation is found; see
+    /// [`localized_message`](Self::localized_message).
+    #[must_use]
+    pub fn localize<L: AsRef<str>>(mut self, locales: &[L]) -> Self {
+        if let Cow::Owned(message) = self.localized_message(locales) {
+            self.message = message;
+        }
+        self
+    }
+
+    /// Render the message for the preferred locales with a catalog
+    ///
+    /// Each locale is tried in order, from most to least specific: `de-CH-1996`,
+    /// then `de-CH`, then `de`. Falls back to the literal message if the error
+    /// has no message key or no locale has a translation.
+    #[must_use]
+    pub fn localized_message_with<L: AsRef<str>>(
+        &self,
+        catalog: &dyn MessageCatalog,
+        locales: &[L],
+    ) -> Cow<'_, str> {
+        let Some(key) = self.message_key() else {
+            return Cow::Borrowed(&self.message);
+        };
+        locales
+            .iter()
+            .flat_map(|locale| locale_fallbacks(locale.as_ref()))
+            .find_map(|locale| catalog.message(locale, key, &self.context))
+            .map_or(Cow::Borrowed(&self.message), Cow::Owned)
+    }
+}
+
+/// Normalize a locale for lookup: lowercase, with `-` as the separator
+fn normalize_locale(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+/// Iterate over a locale and its less specific parents, such as `de-CH`, then `de`
+fn locale_fallbacks(locale: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(locale), |locale| {
+        locale.rfind(['-', '_']).map(|index| &locale[..index])
+    })
+}
+
+/// Substitute `{name}` arguments in a pattern
+fn interpolate(pattern: &str, args: &Context) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(index) = rest.find(['{', '}']) {
+        out.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if let Some(tail) = rest.strip_prefix("{{") {
+            out.push('{');
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("}}") {
+            out.push('}');
+            rest = tail;
+        } else if let Some(end) = rest.find('}').filter(|_| rest.starts_with('{')) {
+            let name = &rest[1..end];
+            match args.get(name) {
+                Some(_) if args.is_sensitive(name) => out.push_str(REDACTED),
+                Some(value) => {
+                    let _ = write!(out, "{value}");
+                }
+                None => out.push_str(&rest[..=end]),
+            }
+            rest = &rest[end + 1..];
+        } else {
+            // Unbalanced brace, kept literally
+            out.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        Catalog::new()
+            .with_message("en", "user.not_found", "User {id} not found")
+            .with_message("de", "user.not_found", "Benutzer {id} nicht gefunden")
+            .with_message("de-CH", "user.not_found", "Benutzer {id} isch nid gfunde")
+            .with_message("pt_BR", "user.not_found", "Usuário {id} não encontrado")
+    }
+
+    fn error() -> Error {
+        Error::new("User 42 not found")
+            .with_message_key("user.not_found")
+            .with_context_value("id", 42)
+    }
+
+    #[test]
+    fn test_locale_fallbacks() {
+        let err = error();
+        let catalog = catalog();
+        let render = |locales: &[&str]| err.localized_message_with(&catalog, locales).into_owned();
+
+        assert_eq!(render(&["de-CH"]), "Benutzer 42 isch nid gfunde");
+        assert_eq!(render(&["de-AT"]), "Benutzer 42 nicht gefunden");
+        assert_eq!(render(&["DE_at"]), "Benutzer 42 nicht gefunden");
+        assert_eq!(render(&["pt-BR"]), "Usuário 42 não encontrado");
+        assert_eq!(render(&["ja", "en-US"]), "User 42 not found");
+        assert_eq!(render(&["ja"]), "User 42 not found");
+        assert_eq!(render(&[]), "User 42 not found");
+    }
+
+    #[test]
+    fn test_literal_fallback() {
+        let catalog = catalog();
+
+        let err = Error::new("plain");
+        assert!(matches!(
+            err.localized_message_with(&catalog, &["en"]),
+            Cow::Borrowed("plain")
+        ));
+
+        let err = Error::new("literal").with_message_key("missing.key");
+        assert_eq!(err.localized_message_with(&catalog, &["en"]), "literal");
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let mut args = Context::new();
+        args.insert("name", "alice");
+        args.insert("count", 3);
+        args.insert_sensitive("token", "abc");
+
+        assert_eq!(interpolate("Hi {name}!", &args), "Hi alice!");
+        assert_eq!(interpolate("{count}{count}", &args), "33");
+        assert_eq!(interpolate("{{name}} is {name}", &args), "{name} is alice");
+        assert_eq!(interpolate("Missing {other}", &args), "Missing {other}");
+        assert_eq!(interpolate("Token {token}", &args), "Token [REDACTED]");
+        assert_eq!(
+            interpolate("Unbalanced { and }", &args),
+            "Unbalanced { and }"
+        );
+        assert_eq!(interpolate("Open {name", &args), "Open {name");
+    }
+
+    #[test]
+    fn test_parse_accept_language() {
+        assert_eq!(
+            parse_accept_language("da, en-GB;q=0.8, en;q=0.7"),
+            ["da", "en-GB", "en"]
+        );
+        assert_eq!(
+            parse_accept_language("en;q=0.5, de;q=0.9, fr"),
+            ["fr", "de", "en"]
+        );
+        assert_eq!(
+            parse_accept_language("en;q=0, *, de;q=abc"),
+            Vec::<String>::new()
+        );
+        assert_eq!(parse_accept_language(" , it ,"), ["it"]);
+        assert!(parse_accept_language("").is_empty());
+    }
+
+    #[test]
+    fn test_catalog_locales() {
+        let catalog = catalog();
+        assert_eq!(catalog.locales(), ["de", "de-ch", "en", "pt-br"]);
+        assert_eq!(
+            catalog.pattern("EN", "user.not_found"),
+            Some("User {id} not found")
+        );
+        assert_eq!(catalog.pattern("en", "other"), None);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_bundles() {
+        let mut catalog = Catalog::new();
+        catalog
+            .add_toml(
+                "en",
+                r#"
+                    greeting = "Hello"
+
+                    [user]
+                    not_found = "User {id} not found"
+
+                    [user.profile]
+                    locked = "Profile locked"
+                "#,
+            )
+            .unwrap();
+        assert_eq!(catalog.pattern("en", "greeting"), Some("Hello"));
+        assert_eq!(
+            catalog.pattern("en", "user.not_found"),
+            Some("User {id} not found")
+        );
+        assert_eq!(
+            catalog.pattern("en", "user.profile.locked"),
+            Some("Profile locked")
+        );
+
+        let err = catalog.add_toml("en", "count = 3").unwrap_err();
+        assert_eq!(err.get_context("key"), Some("count"));
+        assert!(catalog.add_toml("en", "not toml").is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_dir() {
+        let dir = std::env::temp_dir().join(format!("altria-i18n-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("en.toml"), r#"hello = "Hello {name}""#).unwrap();
+        std::fs::write(dir.join("fr-CA.toml"), r#"hello = "Bonjour {name}""#).unwrap();
+        std::fs::write(dir.join("README.md"), "ignored").unwrap();
+
+        let catalog = Catalog::from_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(catalog.locales(), ["en", "fr-ca"]);
+        let err = Error::new("Hello")
+            .with_message_key("hello")
+            .with_context_value("name", "Zoé");
+        assert_eq!(
+            err.localized_message_with(&catalog, &["fr-CA"]),
+            "Bonjour Zoé"
+        );
+
+        assert!(Catalog::from_dir(dir).is_err());
+    }
+}
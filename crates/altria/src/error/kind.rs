@@ -0,0 +1,299 @@
+//! Error categories for programmatic handling
+//!
+//! [`ErrorKind`] classifies an [`Error`] independently of its application-specific
+//! code, so callers can react to "not found" or "timed out" without magic numbers.
+
+use super::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+
+/// Category of an [`Error`]
+///
+/// Each kind has a default HTTP status code, used when the error code
+/// itself is not an HTTP error status.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{Error, ErrorKind};
+///
+/// let err = Error::new("User not found").with_kind(ErrorKind::NotFound);
+/// assert!(err.is_kind(ErrorKind::NotFound));
+/// assert_eq!(err.http_status(), 404);
+///
+/// // Application-specific codes are kept alongside the kind
+/// let err = Error::new("Quota exceeded")
+///     .with_kind(ErrorKind::RateLimited)
+///     .with_code(10_001);
+/// assert_eq!(err.code(), Some(10_001));
+/// assert_eq!(err.http_status(), 429);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The requested resource does not exist
+    NotFound,
+    /// The input is malformed or fails validation
+    InvalidInput,
+    /// The caller is not authenticated
+    Unauthorized,
+    /// The caller is authenticated but not allowed to perform the operation
+    Forbidden,
+    /// The operation conflicts with the current state of the resource
+    Conflict,
+    /// The caller sent too many requests
+    RateLimited,
+    /// A backend or dependency is temporarily unavailable
+    Unavailable,
+    /// The operation did not complete in time
+    Timeout,
+    /// The operation is not implemented
+    Unimplemented,
+    /// An unexpected internal failure
+    Internal,
+}
+
+impl ErrorKind {
+    /// Get the default HTTP status code for this kind
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::ErrorKind;
+    ///
+    /// assert_eq!(ErrorKind::Conflict.http_status(), 409);
+    /// assert_eq!(ErrorKind::Timeout.http_status(), 504);
+    /// ```
+    #[must_use]
+    pub const fn http_status(self) -> u16 {
+        match self {
+            Self::InvalidInput => 400,
+            Self::Unauthorized => 401,
+            Self::Forbidden => 403,
+            Self::NotFound => 404,
+            Self::Conflict => 409,
+            Self::RateLimited => 429,
+            Self::Internal => 500,
+            Self::Unimplemented => 501,
+            Self::Unavailable => 503,
+            Self::Timeout => 504,
+        }
+    }
+
+    /// Get the `snake_case` name of this kind, as used in serialized output
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::NotFound => "not_found",
+            Self::InvalidInput => "invalid_input",
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden => "forbidden",
+            Self::Conflict => "conflict",
+            Self::RateLimited => "rate_limited",
+            Self::Unavailable => "unavailable",
+            Self::Timeout => "timeout",
+            Self::Unimplemented => "unimplemented",
+            Self::Internal => "internal",
+        }
+    }
+
+    /// Map an I/O error kind to an error kind, if there is a meaningful match
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::ErrorKind;
+    /// use std::io;
+    ///
+    /// assert_eq!(ErrorKind::from_io(io::ErrorKind::NotFound), Some(ErrorKind::NotFound));
+    /// assert_eq!(ErrorKind::from_io(io::ErrorKind::TimedOut), Some(ErrorKind::Timeout));
+    /// assert_eq!(ErrorKind::from_io(io::ErrorKind::Other), None);
+    /// ```
+    #[must_use]
+    pub const fn from_io(kind: io::ErrorKind) -> Option<Self> {
+        Some(match kind {
+            io::ErrorKind::NotFound => Self::NotFound,
+            io::ErrorKind::PermissionDenied => Self::Forbidden,
+            io::ErrorKind::AlreadyExists => Self::Conflict,
+            io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData => Self::InvalidInput,
+            io::ErrorKind::TimedOut => Self::Timeout,
+            io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected => Self::Unavailable,
+            io::ErrorKind::Unsupported => Self::Unimplemented,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Error {
+    /// Set the error kind (builder pattern)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{Error, ErrorKind};
+    ///
+    /// let err = Error::new("Email already registered").with_kind(ErrorKind::Conflict);
+    /// assert_eq!(err.kind(), Some(ErrorKind::Conflict));
+    /// ```
+    #[must_use]
+    pub const fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Get the error kind
+    #[must_use]
+    pub const fn kind(&self) -> Option<ErrorKind> {
+        self.kind
+    }
+
+    /// Check whether this error is of the given kind
+    #[must_use]
+    pub fn is_kind(&self, kind: ErrorKind) -> bool {
+        self.kind == Some(kind)
+    }
+
+    /// Set the HTTP status code explicitly (builder pattern)
+    ///
+    /// Useful for application-specific codes, which are not HTTP statuses
+    /// themselves. Statuses outside `400..=599` are ignored by
+    /// [`http_status`](Self::http_status).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("Card declined").with_code(20_003).with_http_status(402);
+    /// assert_eq!(err.http_status(), 402);
+    /// ```
+    #[must_use]
+    pub fn with_http_status(mut self, status: u16) -> Self {
+        self.extra_mut().http_status = Some(status);
+        self
+    }
+
+    /// Get the HTTP status code for this error
+    ///
+    /// Resolution order:
+    /// 1. The explicit status set with [`with_http_status`](Self::with_http_status)
+    /// 2. The error code, if it is an HTTP error status (`400..=599`)
+    /// 3. The default status of the error kind
+    /// 4. `500`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{Error, ErrorKind};
+    ///
+    /// assert_eq!(Error::new("a").with_code(404).http_status(), 404);
+    /// assert_eq!(Error::new("b").with_kind(ErrorKind::Forbidden).http_status(), 403);
+    /// assert_eq!(Error::new("c").with_code(10_001).http_status(), 500);
+    /// ```
+    #[must_use]
+    pub fn http_status(&self) -> u16 {
+        let is_error_status = |status: &u16| (400..=599).contains(status);
+        self.extra
+            .as_ref()
+            .and_then(|extra| extra.http_status)
+            .filter(is_error_status)
+            .or_else(|| {
+                self.code
+                    .and_then(|code| u16::try_from(code).ok())
+                    .filter(is_error_status)
+            })
+            .or_else(|| self.kind.map(ErrorKind::http_status))
+            .unwrap_or(500)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_default() {
+        let err = Error::new("plain");
+        assert_eq!(err.kind(), None);
+        assert!(!err.is_kind(ErrorKind::Internal));
+        assert_eq!(err.http_status(), 500);
+    }
+
+    #[test]
+    fn test_kind_http_status() {
+        let cases = [
+            (ErrorKind::NotFound, 404),
+            (ErrorKind::InvalidInput, 400),
+            (ErrorKind::Unauthorized, 401),
+            (ErrorKind::Forbidden, 403),
+            (ErrorKind::Conflict, 409),
+            (ErrorKind::RateLimited, 429),
+            (ErrorKind::Unavailable, 503),
+            (ErrorKind::Timeout, 504),
+            (ErrorKind::Unimplemented, 501),
+            (ErrorKind::Internal, 500),
+        ];
+        for (kind, status) in cases {
+            assert_eq!(Error::new("e").with_kind(kind).http_status(), status);
+        }
+    }
+
+    #[test]
+    fn test_http_code_takes_precedence() {
+        let err = Error::new("e")
+            .with_kind(ErrorKind::NotFound)
+            .with_code(410);
+        assert_eq!(err.http_status(), 410);
+
+        let err = Error::new("e").with_kind(ErrorKind::NotFound).with_code(-1);
+        assert_eq!(err.http_status(), 404);
+    }
+
+    #[test]
+    fn test_explicit_http_status() {
+        let err = Error::new("e")
+            .with_kind(ErrorKind::Forbidden)
+            .with_code(409)
+            .with_http_status(402);
+        assert_eq!(err.http_status(), 402);
+
+        let err = Error::new("e")
+            .with_kind(ErrorKind::NotFound)
+            .with_code(410)
+            .with_http_status(200);
+        assert_eq!(err.http_status(), 410);
+    }
+
+    #[test]
+    fn test_kind_serde_names() {
+        for kind in [
+            ErrorKind::NotFound,
+            ErrorKind::RateLimited,
+            ErrorKind::InvalidInput,
+        ] {
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(json, format!("\"{}\"", kind.as_str()));
+            assert_eq!(serde_json::from_str::<ErrorKind>(&json).unwrap(), kind);
+        }
+    }
+
+    #[test]
+    fn test_io_conversion_sets_kind() {
+        let err: Error = io::Error::new(io::ErrorKind::NotFound, "missing").into();
+        assert!(err.is_kind(ErrorKind::NotFound));
+
+        let err: Error = io::Error::other("other").into();
+        assert_eq!(err.kind(), None);
+    }
+}
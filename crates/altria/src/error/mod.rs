@@ -0,0 +1,1217 @@
+//! Error handling module for Altria
+//!
+//! Provides a flexible and efficient error type with the following features:
+//! - Optional error code for API integration, with declared code registries ([`error_codes!`](crate::error_codes))
+//! - Optional error kind for programmatic handling ([`ErrorKind`])
+//! - Retry classification with optional `retry_after` hint ([`Retryability`])
+//! - Required error message, with optional localization through message catalogs ([`Catalog`])
+//! - Optional source error for error chaining, with downcasting helpers ([`Error::find_source`])
+//! - Optional backtrace for debugging, with a global capture policy ([`BacktracePolicy`])
+//! - Automatic call-site location capture via `#[track_caller]`
+//! - Optional `tracing` integration: structured events and span trace capture (`tracing` feature)
+//! - Compact and pretty reports of the full source chain ([`Report`])
+//! - Typed, insertion-ordered context key-value pairs ([`Context`], [`ContextValue`])
+//! - Redaction of sensitive context values in all renderings ([`set_redacted_keys`])
+//! - Thread-safe and Send + Sync compatible
+//! - Cheap `Clone`: the source chain and backtrace are shared behind `Arc`
+//! - Aggregation of independent errors ([`ErrorSet`], [`Error::from_many`])
+//! - Field-level validation error aggregation ([`ValidationErrors`])
+//! - RFC 9457 Problem Details rendering via the [`problem`] module
+//! - Serde support with a stable wire format (see [`OpaqueError`])
+//! - Extension traits for attaching context to foreign errors ([`ResultExt`], [`OptionExt`])
+
+mod aggregate;
+mod backtrace;
+mod context;
+mod downcast;
+mod ext;
+mod i18n;
+mod kind;
+pub mod problem;
+mod redact;
+mod registry;
+mod report;
+mod retry;
+#[cfg(feature = "tracing")]
+mod trace;
+mod validation;
+mod wire;
+
+pub use self::backtrace::{BacktracePolicy, backtrace_policy, set_backtrace_policy};
+pub use aggregate::ErrorSet;
+pub use context::{
+    Context, ContextIntoIter, ContextIter, ContextOrder, ContextValue, context_order,
+    set_context_order,
+};
+pub use ext::{OptionExt, ResultExt};
+pub use i18n::{
+    Catalog, MessageCatalog, message_catalog, parse_accept_language, set_message_catalog,
+};
+pub use kind::ErrorKind;
+pub use redact::{REDACTED, redacted_keys, set_redacted_keys};
+pub use registry::{ErrorCode, ErrorCodeInfo, ErrorRegistry};
+pub use report::{Report, ReportStyle};
+pub use retry::Retryability;
+pub use validation::{ValidationErrors, Violation};
+pub use wire::OpaqueError;
+
+use context::ContextDisplay;
+use std::backtrace::Backtrace;
+use std::error::Error as StdError;
+use std::fmt;
+use std::panic::Location;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A flexible error type for the Altria library
+///
+/// This error type is designed to be:
+/// - **Efficient**: Uses `Box` and `Arc` for optional fields to minimize size
+/// - **Flexible**: Supports error codes, messages, source errors, and extra context
+/// - **Debuggable**: Captures backtrace information when created
+/// - **Thread-safe**: Implements `Send` and `Sync`
+/// - **Cloneable**: Clones share the source chain and backtrace, so failures can be
+///   cached or broadcast to multiple waiters
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::Error;
+///
+/// // Simple error with just a message
+/// let err = Error::new("Something went wrong");
+///
+/// // Error with code and message
+/// let err = Error::new("Not found").with_code(404);
+///
+/// // Error with context information
+/// let err = Error::new("Database error")
+///     .with_context_value("table", "users")
+///     .with_context_value("operation", "insert");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Error {
+    /// Optional error code (e.g., HTTP status code, custom error code)
+    code: Option<i64>,
+    /// Optional error category for programmatic handling
+    kind: Option<ErrorKind>,
+    /// Explicit retry classification, overriding the derived one
+    retryability: Option<Retryability>,
+    /// Rarely set fields, boxed together to keep `Error` small
+    extra: Option<Box<Extra>>,
+    /// Required error message
+    message: String,
+    /// Optional source error for error chain, shared between clones
+    source: Option<Arc<dyn StdError + Send + Sync>>,
+    /// Optional backtrace for debugging, shared between clones
+    backtrace: Option<Arc<Backtrace>>,
+    /// Whether the backtrace was explicitly requested or suppressed for this error
+    backtrace_overridden: bool,
+    /// Source location where the error was created
+    location: Option<&'static Location<'static>>,
+    /// Span trace captured where the error was created
+    #[cfg(feature = "tracing")]
+    span_trace: Option<Box<tracing_error::SpanTrace>>,
+    /// Context key-value pairs for additional information
+    context: Context,
+}
+
+/// Rarely set fields of [`Error`]
+#[derive(Debug, Clone, Default)]
+struct Extra {
+    /// Suggested delay before retrying
+    retry_after: Option<Duration>,
+    /// Catalog key of the localized message
+    message_key: Option<String>,
+    /// Explicit HTTP status, overriding the one derived from the code and kind
+    http_status: Option<u16>,
+}
+
+impl Error {
+    /// Create a new error with just a message
+    ///
+    /// By default, backtrace is not captured for performance.
+    /// Use [`with_backtrace`](Self::with_backtrace) to enable it, or set a
+    /// global [`BacktracePolicy`] to capture automatically.
+    ///
+    /// The caller's source location is always recorded; see [`location`](Self::location).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("Something went wrong");
+    /// assert_eq!(err.message(), "Something went wrong");
+    /// assert!(err.backtrace().is_none()); // No backtrace by default
+    /// ```
+    #[track_caller]
+    pub fn new(message: impl Into<String>) -> Self {
+        let mut err = Self::bare(message.into());
+        err.location = Some(Location::caller());
+        #[cfg(feature = "tracing")]
+        {
+            err.span_trace = trace::capture_span_trace();
+        }
+        err.apply_backtrace_policy();
+        err
+    }
+
+    /// Create an error without capturing a location, backtrace or span trace
+    ///
+    /// For errors rebuilt from elsewhere, whose origin is not the current code.
+    fn bare(message: String) -> Self {
+        Self {
+            code: None,
+            kind: None,
+            retryability: None,
+            extra: None,
+            message,
+            source: None,
+            backtrace: None,
+            backtrace_overridden: false,
+            location: None,
+            #[cfg(feature = "tracing")]
+            span_trace: None,
+            context: Context::new(),
+        }
+    }
+
+    /// Add an error code (builder pattern)
+    ///
+    /// This `const` setter bypasses the global [`BacktracePolicy`]: a
+    /// [`BacktracePolicy::OnlyForCodes`] policy captures nothing for codes set
+    /// this way. Use [`with_code_traced`](Self::with_code_traced) to apply it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("Error").with_code(500);
+    /// assert_eq!(err.code(), Some(500));
+    /// ```
+    #[must_use]
+    pub const fn with_code(mut self, code: i64) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Add an error code, applying the global backtrace policy (builder pattern)
+    ///
+    /// Captures a backtrace if the policy is [`BacktracePolicy::OnlyForCodes`]
+    /// and the code is in range. The [`error!`](crate::error!) macro and
+    /// [`ResultExt::with_code`] set codes this way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{set_backtrace_policy, BacktracePolicy, Error};
+    ///
+    /// set_backtrace_policy(BacktracePolicy::OnlyForCodes(500..=599));
+    ///
+    /// let err = Error::new("Database down").with_code_traced(503);
+    /// assert_eq!(err.code(), Some(503));
+    /// assert!(err.backtrace().is_some());
+    /// ```
+    #[must_use]
+    pub fn with_code_traced(mut self, code: i64) -> Self {
+        self.code = Some(code);
+        self.apply_backtrace_policy_for_code(code);
+        self
+    }
+
+    /// Add a source error (builder pattern)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use std::io;
+    ///
+    /// let io_err = io::Error::new(io::ErrorKind::NotFound, "file not found");
+    /// let err = Error::new("Failed to read file").with_source(io_err);
+    /// ```
+    #[must_use]
+    pub fn with_source(mut self, source: impl StdError + Send + Sync + 'static) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    /// Add a shared source error (builder pattern)
+    ///
+    /// Lets several errors share one source without cloning it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use std::error::Error as StdError;
+    /// use std::io;
+    /// use std::sync::Arc;
+    ///
+    /// let cause: Arc<dyn StdError + Send + Sync> = Arc::new(io::Error::other("disk full"));
+    /// let a = Error::new("Write failed").with_shared_source(cause.clone());
+    /// let b = Error::new("Flush failed").with_shared_source(cause);
+    /// assert_eq!(a.source().unwrap().to_string(), b.source().unwrap().to_string());
+    /// ```
+    #[must_use]
+    pub fn with_shared_source(mut self, source: Arc<dyn StdError + Send + Sync>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Add a single context key-value pair (builder pattern)
+    ///
+    /// Values can be strings, numbers, booleans, lists or maps (see [`ContextValue`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("Database error")
+    ///     .with_context_value("table", "users")
+    ///     .with_context_value("operation", "insert")
+    ///     .with_context_value("rows", 3);
+    /// ```
+    #[must_use]
+    pub fn with_context_value(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<ContextValue>,
+    ) -> Self {
+        self.context.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a sensitive context key-value pair (builder pattern)
+    ///
+    /// The value is rendered as `[REDACTED]` by `Display`, `Debug`, serialization
+    /// and HTTP responses, but remains available through
+    /// [`get_context_value`](Self::get_context_value).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("Invalid credentials")
+    ///     .with_context_value("user", "alice")
+    ///     .with_sensitive_context("email", "alice@example.com");
+    ///
+    /// assert_eq!(err.to_string(), "Invalid credentials (user: alice, email: [REDACTED])");
+    /// assert!(!format!("{err:?}").contains("alice@example.com"));
+    /// assert_eq!(err.get_context("email"), Some("alice@example.com"));
+    /// ```
+    #[must_use]
+    pub fn with_sensitive_context(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<ContextValue>,
+    ) -> Self {
+        self.context.insert_sensitive(key, value);
+        self
+    }
+
+    /// Add multiple context key-value pairs at once from a map or iterator of pairs
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut ctx = HashMap::new();
+    /// ctx.insert("table".to_string(), "users".to_string());
+    /// ctx.insert("operation".to_string(), "insert".to_string());
+    ///
+    /// let err = Error::new("Database error").with_context_map(ctx);
+    /// assert_eq!(err.get_context("table"), Some("users"));
+    /// ```
+    #[must_use]
+    pub fn with_context_map<K, V>(mut self, context: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<ContextValue>,
+    {
+        self.context.extend(context);
+        self
+    }
+
+    /// Enable backtrace capture for debugging
+    ///
+    /// Backtrace is disabled by default for performance. Use this method
+    /// when you need detailed stack trace information for debugging.
+    /// The backtrace is taken regardless of the global [`BacktracePolicy`], but
+    /// like [`Backtrace::capture`] it is only captured when
+    /// `RUST_LIB_BACKTRACE` / `RUST_BACKTRACE` enable it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("Error").with_backtrace();
+    /// assert!(err.backtrace().is_some());
+    /// ```
+    #[must_use]
+    pub fn with_backtrace(mut self) -> Self {
+        self.backtrace = Some(Arc::new(Backtrace::capture()));
+        self.backtrace_overridden = true;
+        self
+    }
+
+    /// Drop any captured backtrace and opt out of the global [`BacktracePolicy`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{set_backtrace_policy, BacktracePolicy, Error};
+    ///
+    /// set_backtrace_policy(BacktracePolicy::OnlyForCodes(500..=599));
+    ///
+    /// let err = Error::new("Expected failure").without_backtrace().with_code_traced(503);
+    /// assert!(err.backtrace().is_none());
+    /// ```
+    #[must_use]
+    pub fn without_backtrace(mut self) -> Self {
+        self.backtrace = None;
+        self.backtrace_overridden = true;
+        self
+    }
+
+    /// Get the error code
+    #[must_use]
+    pub const fn code(&self) -> Option<i64> {
+        self.code
+    }
+
+    /// Get the error message
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Get the backtrace if available
+    #[must_use]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+
+    /// Get the source location where this error was created
+    ///
+    /// Recorded by [`Error::new`], the [`error!`](crate::error!) family of macros
+    /// and the `From` conversions. Errors rebuilt by deserialization have no location.
+    ///
+    /// The alternate `Display` format (`{:#}`) appends the location.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("Something went wrong");
+    /// let location = err.location().unwrap();
+    /// assert_eq!(location.file(), file!());
+    /// assert_eq!(location.line(), line!() - 3);
+    ///
+    /// assert!(format!("{err:#}").ends_with(&format!(" at {location}")));
+    /// ```
+    #[must_use]
+    pub const fn location(&self) -> Option<&'static Location<'static>> {
+        self.location
+    }
+
+    /// Get all context information in insertion order
+    #[must_use]
+    pub const fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Get a specific context value by key as a string
+    ///
+    /// Values that are not strings are rendered with their `Display` form. Use
+    /// [`get_context_value`](Self::get_context_value) for typed values.
+    #[must_use]
+    pub fn get_context(&self, key: &str) -> Option<&str> {
+        self.context.get_str(key)
+    }
+
+    /// Get a specific typed context value by key
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{ContextValue, Error};
+    ///
+    /// let err = Error::new("Retry limit reached").with_context_value("attempts", 5);
+    /// assert_eq!(err.get_context_value("attempts"), Some(&ContextValue::Int(5)));
+    /// assert_eq!(err.get_context("attempts"), Some("5")); // Rendered with `Display`
+    /// ```
+    #[must_use]
+    pub fn get_context_value(&self, key: &str) -> Option<&ContextValue> {
+        self.context.get(key)
+    }
+
+    /// Returns an iterator over the entire error chain, starting from this error
+    ///
+    /// This iterator includes the current error as the first item,
+    /// followed by all source errors in the chain. For aggregate errors
+    /// (see [`Error::from_many`]), each child and its own chain follow in turn.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use std::error::Error as StdError;
+    /// use std::io;
+    ///
+    /// let io_err = io::Error::new(io::ErrorKind::NotFound, "file.txt not found");
+    /// let err = Error::new("Failed to read config")
+    ///     .with_source(io_err)
+    ///     .with_code(500);
+    ///
+    /// let chain: Vec<String> = err.iter_error_chain()
+    ///     .map(|e| e.to_string())
+    ///     .collect();
+    ///
+    /// assert_eq!(chain.len(), 2); // Current error + 1 source
+    /// assert!(chain[0].contains("Failed to read config"));
+    /// ```
+    #[must_use]
+    pub fn iter_error_chain(&self) -> ErrorChainIter<'_> {
+        ErrorChainIter { stack: vec![self] }
+    }
+
+    /// Write the code, message and context
+    pub(super) fn fmt_header(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(code) = self.code {
+            write!(f, "[{}] {}", code, self.message)?;
+        } else {
+            write!(f, "{}", self.message)?;
+        }
+
+        write!(
+            f,
+            "{}",
+            ContextDisplay {
+                context: &self.context,
+                order: context_order(),
+            }
+        )
+    }
+
+    /// Get the rarely set fields, allocating them on first use
+    fn extra_mut(&mut self) -> &mut Extra {
+        self.extra.get_or_insert_with(Box::default)
+    }
+}
+
+/// Iterator over the complete error chain
+///
+/// Created by the [`Error::iter_error_chain`] method. Iterates over the entire error chain,
+/// starting from the current error and following the source chain depth-first
+/// through the children of aggregate errors.
+#[derive(Debug, Clone)]
+pub struct ErrorChainIter<'a> {
+    /// Errors still to visit, next on top
+    stack: Vec<&'a (dyn StdError + 'static)>,
+}
+
+impl<'a> Iterator for ErrorChainIter<'a> {
+    type Item = &'a (dyn StdError + 'static);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.stack.pop()?;
+        if let Some(source) = current.source() {
+            if let Some(set) = source.downcast_ref::<ErrorSet>() {
+                // Visit the children instead of the set itself
+                self.stack
+                    .extend(set.iter().rev().map(|e| e as &(dyn StdError + 'static)));
+            } else {
+                self.stack.push(source);
+            }
+        }
+        Some(current)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_header(f)?;
+
+        if let Some(set) = self.error_set() {
+            write!(f, ": {set}")?;
+        }
+
+        if f.alternate()
+            && let Some(location) = self.location
+        {
+            write!(f, " at {location}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_deref()
+            .map(|e| e as &(dyn StdError + 'static))
+    }
+}
+
+// Ensure Error is Send + Sync for async safety
+// The compiler will enforce this through the trait bounds on source
+// and the use of standard library types for other fields
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    let _ = assert_send_sync::<Error>;
+};
+
+/// Convenience type alias for Result with our Error type
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Helper macro for creating errors quickly with builder pattern support
+///
+/// # Examples
+///
+/// ```
+/// use altria::error;
+///
+/// // Simple error
+/// let err = error!("Something went wrong");
+///
+/// // Error with code
+/// let err = error!("Not found"; code: 404);
+///
+/// // Error with code and context
+/// let err = error!("Database error"; code: 500, "table" => "users", "op" => "insert");
+///
+/// // Error with format string
+/// let value = "request";
+/// let err = error!("Failed to process {}", value);
+///
+/// // Error with format string and code (use semicolon before code)
+/// let err = error!("User {} not found", "alice"; code: 404);
+/// ```
+#[macro_export]
+macro_rules! error {
+    // Format string with arguments, code, and context fields
+    // error!("msg {}", arg; code: 404, "key" => "value", "key2" => "value2")
+    ($fmt:literal, $($arg:expr),+ ; code: $code:expr, $($key:expr => $value:expr),+ $(,)?) => {{
+        let mut err = $crate::error::Error::new(format!($fmt, $($arg),+))
+            .with_code_traced($code);
+        $(
+            err = err.with_context_value($key, $value);
+        )+
+        err
+    }};
+    // Format string with arguments and code
+    // error!("msg {}", arg; code: 404)
+    ($fmt:literal, $($arg:expr),+ ; code: $code:expr $(,)?) => {
+        $crate::error::Error::new(format!($fmt, $($arg),+))
+            .with_code_traced($code)
+    };
+    // Format string with arguments and context fields
+    // error!("msg {}", arg; "key" => "value")
+    ($fmt:literal, $($arg:expr),+ ; $($key:expr => $value:expr),+ $(,)?) => {{
+        let mut err = $crate::error::Error::new(format!($fmt, $($arg),+));
+        $(
+            err = err.with_context_value($key, $value);
+        )+
+        err
+    }};
+    // Simple message with code and context fields
+    // error!("msg"; code: 404, "key" => "value")
+    ($msg:expr ; code: $code:expr, $($key:expr => $value:expr),+ $(,)?) => {{
+        let mut err = $crate::error::Error::new($msg).with_code_traced($code);
+        $(
+            err = err.with_context_value($key, $value);
+        )+
+        err
+    }};
+    // Simple message with code
+    // error!("msg"; code: 404)
+    ($msg:expr ; code: $code:expr $(,)?) => {
+        $crate::error::Error::new($msg).with_code_traced($code)
+    };
+    // Simple message with context fields
+    // error!("msg"; "key" => "value", "key2" => "value2")
+    ($msg:expr ; $($key:expr => $value:expr),+ $(,)?) => {{
+        let mut err = $crate::error::Error::new($msg);
+        $(
+            err = err.with_context_value($key, $value);
+        )+
+        err
+    }};
+    // Format string with arguments
+    ($fmt:literal, $($arg:expr),+ $(,)?) => {
+        $crate::error::Error::new(format!($fmt, $($arg),+))
+    };
+    // Simple string message
+    ($msg:expr $(,)?) => {
+        $crate::error::Error::new($msg)
+    };
+}
+
+/// Return early with an error built by [`error!`]
+///
+/// Accepts exactly the same arguments as [`error!`]. The error is converted
+/// with [`From`], so `bail!` also works in functions whose error type can be
+/// built from [`Error`].
+///
+/// # Examples
+///
+/// ```
+/// use altria::bail;
+/// use altria::error::Result;
+///
+/// fn find_user(name: &str) -> Result<u64> {
+///     if name.is_empty() {
+///         bail!("Name must not be empty"; code: 400);
+///     }
+///     if name != "alice" {
+///         bail!("User {} not found", name; code: 404, "name" => name);
+///     }
+///     Ok(1)
+/// }
+///
+/// assert_eq!(find_user("alice").unwrap(), 1);
+/// assert_eq!(find_user("").unwrap_err().code(), Some(400));
+/// assert_eq!(find_user("bob").unwrap_err().message(), "User bob not found");
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($($arg:tt)+) => {
+        return ::core::result::Result::Err(::core::convert::From::from($crate::error!($($arg)+)))
+    };
+}
+
+/// Return early with an error if a condition is not satisfied
+///
+/// The first argument is the condition; the remaining arguments are the same
+/// as for [`error!`]. Without a message, the error describes the failed condition.
+///
+/// # Examples
+///
+/// ```
+/// use altria::ensure;
+/// use altria::error::Result;
+///
+/// fn check_age(age: u32) -> Result<()> {
+///     ensure!(age >= 18, "Age must be at least 18, got {}", age; code: 422, "field" => "age");
+///     Ok(())
+/// }
+///
+/// fn check_len(items: &[u8]) -> Result<()> {
+///     ensure!(!items.is_empty());
+///     Ok(())
+/// }
+///
+/// let err = check_age(16).unwrap_err();
+/// assert_eq!(err.code(), Some(422));
+/// assert_eq!(err.get_context("field"), Some("age"));
+///
+/// let err = check_len(&[]).unwrap_err();
+/// assert_eq!(err.message(), "Condition failed: `!items.is_empty()`");
+/// ```
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::bail!($($arg)+);
+        }
+    };
+    ($cond:expr $(,)?) => {
+        $crate::ensure!($cond, concat!("Condition failed: `", stringify!($cond), "`"))
+    };
+}
+
+// Implement From for common error types for easy conversion
+impl From<std::io::Error> for Error {
+    #[track_caller]
+    fn from(err: std::io::Error) -> Self {
+        ext::wrap("I/O error".to_string(), Box::new(err))
+    }
+}
+
+impl From<std::fmt::Error> for Error {
+    #[track_caller]
+    fn from(err: std::fmt::Error) -> Self {
+        Self::new("Formatting error").with_source(err)
+    }
+}
+
+impl From<String> for Error {
+    #[track_caller]
+    fn from(s: String) -> Self {
+        Self::new(s)
+    }
+}
+
+impl From<&str> for Error {
+    #[track_caller]
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_error() {
+        let err = Error::new("test error");
+        assert_eq!(err.message(), "test error");
+        assert_eq!(err.code(), None);
+        assert!(err.backtrace().is_none()); // No backtrace by default
+    }
+
+    #[test]
+    fn test_error_with_backtrace() {
+        let err = Error::new("test error").with_backtrace();
+        assert!(err.backtrace().is_some());
+    }
+
+    #[test]
+    fn test_error_with_code() {
+        let err = Error::new("not found").with_code(404);
+        assert_eq!(err.message(), "not found");
+        assert_eq!(err.code(), Some(404));
+    }
+
+    #[test]
+    fn test_error_with_code_is_const() {
+        const fn not_found(err: Error) -> Error {
+            err.with_code(404)
+        }
+        assert_eq!(not_found(Error::new("missing")).code(), Some(404));
+        assert_eq!(
+            Error::new("missing").with_code_traced(404).code(),
+            Some(404)
+        );
+    }
+
+    #[test]
+    fn test_error_with_context() {
+        let err = Error::new("database error")
+            .with_context_value("table", "users")
+            .with_context_value("operation", "insert");
+
+        assert_eq!(err.get_context("table"), Some("users"));
+        assert_eq!(err.get_context("operation"), Some("insert"));
+        assert_eq!(err.get_context("nonexistent"), None);
+    }
+
+    #[test]
+    fn test_error_with_typed_context() {
+        let err = Error::new("upload failed")
+            .with_context_value("size", 1024_u32)
+            .with_context_value("retry", true)
+            .with_context_value("name", String::from("a.txt"));
+
+        assert_eq!(
+            err.get_context_value("size"),
+            Some(&ContextValue::Int(1024))
+        );
+        assert_eq!(
+            err.get_context_value("retry"),
+            Some(&ContextValue::Bool(true))
+        );
+        assert_eq!(err.get_context("size"), Some("1024"));
+        assert_eq!(err.get_context("retry"), Some("true"));
+        assert_eq!(err.get_context("name"), Some("a.txt"));
+        assert_eq!(err.get_context("missing"), None);
+
+        // Replacing a value renders the new one
+        let err = err.with_context_value("size", 2048);
+        assert_eq!(err.get_context("size"), Some("2048"));
+
+        let err = error!("failed"; "attempt" => 3, "ok" => false);
+        assert_eq!(
+            err.get_context_value("attempt"),
+            Some(&ContextValue::Int(3))
+        );
+        assert_eq!(
+            err.get_context_value("ok"),
+            Some(&ContextValue::Bool(false))
+        );
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = Error::new("internal error").with_code(500);
+        assert_eq!(err.to_string(), "[500] internal error");
+
+        let err = Error::new("simple error");
+        assert_eq!(err.to_string(), "simple error");
+
+        let err = Error::new("error").with_context_value("key", "value");
+        assert_eq!(err.to_string(), "error (key: value)");
+
+        // Multi-key context renders in insertion order
+        let err = Error::new("error")
+            .with_code(500)
+            .with_context_value("b", 2)
+            .with_context_value("a", "one")
+            .with_context_value("c", true);
+        assert_eq!(err.to_string(), "[500] error (b: 2, a: one, c: true)");
+    }
+
+    #[test]
+    fn test_error_source() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
+        let err = Error::new("failed to read").with_source(io_err);
+
+        assert!(err.source().is_some());
+        assert_eq!(err.message(), "failed to read");
+    }
+
+    #[test]
+    fn test_clone_shares_source_and_backtrace() {
+        let err = Error::new("outer")
+            .with_code(500)
+            .with_context_value("id", 1)
+            .with_source(Error::new("inner").with_source(std::io::Error::other("io")))
+            .with_backtrace();
+        let cloned = err.clone();
+
+        assert_eq!(cloned.to_string(), err.to_string());
+        assert_eq!(cloned.iter_error_chain().count(), 3);
+        assert!(Arc::ptr_eq(
+            cloned.source.as_ref().unwrap(),
+            err.source.as_ref().unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            cloned.backtrace.as_ref().unwrap(),
+            err.backtrace.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_clone_across_threads() {
+        fn assert_send_sync_clone<T: Send + Sync + Clone + 'static>() {}
+        assert_send_sync_clone::<Error>();
+
+        let err = Error::new("shared").with_source(std::io::Error::other("io"));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let err = err.clone();
+                std::thread::spawn(move || err.iter_error_chain().count())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 2);
+        }
+
+        let shared: Arc<dyn StdError + Send + Sync> = Arc::new(std::io::Error::other("io"));
+        let a = Error::new("a").with_shared_source(shared.clone());
+        assert!(Arc::ptr_eq(a.source.as_ref().unwrap(), &shared));
+    }
+
+    #[test]
+    fn test_error_chain_iterator() {
+        use std::io;
+
+        // Chain with source
+        let io_err = io::Error::new(io::ErrorKind::NotFound, "file.txt not found");
+        let err = Error::new("Failed to read config").with_source(io_err);
+
+        let chain: Vec<_> = err.iter_error_chain().collect();
+        assert_eq!(chain.len(), 2); // Current error + 1 source
+
+        // No source - only current error
+        let err = Error::new("simple error");
+        let chain: Vec<_> = err.iter_error_chain().collect();
+        assert_eq!(chain.len(), 1); // Only current error
+    }
+
+    #[test]
+    fn test_error_macro() {
+        let err = error!("simple");
+        assert_eq!(err.message(), "simple");
+
+        let err = error!("not found"; code: 404);
+        assert_eq!(err.code(), Some(404));
+        assert_eq!(err.message(), "not found");
+
+        let value = "test";
+        let err = error!("format {}", value);
+        assert_eq!(err.message(), "format test");
+
+        let err = error!("error"; "key" => "value", "key2" => "value2");
+        assert_eq!(err.get_context("key"), Some("value"));
+        assert_eq!(err.get_context("key2"), Some("value2"));
+
+        let err = error!("error"; code: 500, "table" => "users");
+        assert_eq!(err.code(), Some(500));
+        assert_eq!(err.get_context("table"), Some("users"));
+
+        let err = error!("user {}", "alice"; code: 404);
+        assert_eq!(err.message(), "user alice");
+        assert_eq!(err.code(), Some(404));
+    }
+
+    #[test]
+    fn test_bail_macro() {
+        fn simple() -> Result<()> {
+            bail!("simple");
+        }
+        fn format_args(value: &str) -> Result<()> {
+            bail!("format {}", value);
+        }
+        fn format_code(value: &str) -> Result<()> {
+            bail!("user {}", value; code: 404);
+        }
+        fn format_context(value: &str) -> Result<()> {
+            bail!("user {}", value; "key" => "value");
+        }
+        fn format_code_context(value: &str) -> Result<()> {
+            bail!("user {}", value; code: 404, "key" => "value", "key2" => "value2");
+        }
+        fn code() -> Result<()> {
+            bail!("not found"; code: 404);
+        }
+        fn context() -> Result<()> {
+            bail!("error"; "key" => "value", "key2" => "value2");
+        }
+        fn code_context() -> Result<()> {
+            bail!("error"; code: 500, "table" => "users");
+        }
+        fn converted() -> std::result::Result<(), Box<dyn StdError>> {
+            bail!("boxed"; code: 500);
+        }
+
+        let err = simple().unwrap_err();
+        assert_eq!(err.message(), "simple");
+        assert_eq!(err.code(), None);
+
+        let err = format_args("test").unwrap_err();
+        assert_eq!(err.message(), "format test");
+
+        let err = format_code("alice").unwrap_err();
+        assert_eq!(err.message(), "user alice");
+        assert_eq!(err.code(), Some(404));
+
+        let err = format_context("alice").unwrap_err();
+        assert_eq!(err.message(), "user alice");
+        assert_eq!(err.get_context("key"), Some("value"));
+
+        let err = format_code_context("alice").unwrap_err();
+        assert_eq!(err.message(), "user alice");
+        assert_eq!(err.code(), Some(404));
+        assert_eq!(err.get_context("key"), Some("value"));
+        assert_eq!(err.get_context("key2"), Some("value2"));
+
+        let err = code().unwrap_err();
+        assert_eq!(err.message(), "not found");
+        assert_eq!(err.code(), Some(404));
+
+        let err = context().unwrap_err();
+        assert_eq!(err.get_context("key"), Some("value"));
+        assert_eq!(err.get_context("key2"), Some("value2"));
+
+        let err = code_context().unwrap_err();
+        assert_eq!(err.code(), Some(500));
+        assert_eq!(err.get_context("table"), Some("users"));
+
+        let err = converted().unwrap_err();
+        assert_eq!(err.to_string(), "[500] boxed");
+    }
+
+    #[test]
+    fn test_ensure_macro() {
+        fn check(cond: bool) -> Result<()> {
+            ensure!(cond);
+            Ok(())
+        }
+        fn simple(cond: bool) -> Result<()> {
+            ensure!(cond, "simple");
+            Ok(())
+        }
+        fn format_args(cond: bool, value: &str) -> Result<()> {
+            ensure!(cond, "format {}", value);
+            Ok(())
+        }
+        fn format_code(cond: bool, value: &str) -> Result<()> {
+            ensure!(cond, "user {}", value; code: 404);
+            Ok(())
+        }
+        fn format_context(cond: bool, value: &str) -> Result<()> {
+            ensure!(cond, "user {}", value; "key" => "value");
+            Ok(())
+        }
+        fn format_code_context(cond: bool, value: &str) -> Result<()> {
+            ensure!(cond, "user {}", value; code: 404, "key" => "value", "key2" => "value2");
+            Ok(())
+        }
+        fn code(cond: bool) -> Result<()> {
+            ensure!(cond, "not found"; code: 404);
+            Ok(())
+        }
+        fn context(cond: bool) -> Result<()> {
+            ensure!(cond, "error"; "key" => "value", "key2" => "value2");
+            Ok(())
+        }
+        fn code_context(cond: bool) -> Result<()> {
+            ensure!(cond, "error"; code: 500, "table" => "users");
+            Ok(())
+        }
+
+        assert!(check(true).is_ok());
+        assert!(simple(true).is_ok());
+        assert!(format_args(true, "test").is_ok());
+        assert!(format_code(true, "alice").is_ok());
+        assert!(format_context(true, "alice").is_ok());
+        assert!(format_code_context(true, "alice").is_ok());
+        assert!(code(true).is_ok());
+        assert!(context(true).is_ok());
+        assert!(code_context(true).is_ok());
+
+        let err = check(false).unwrap_err();
+        assert_eq!(err.message(), "Condition failed: `cond`");
+
+        let err = simple(false).unwrap_err();
+        assert_eq!(err.message(), "simple");
+
+        let err = format_args(false, "test").unwrap_err();
+        assert_eq!(err.message(), "format test");
+
+        let err = format_code(false, "alice").unwrap_err();
+        assert_eq!(err.message(), "user alice");
+        assert_eq!(err.code(), Some(404));
+
+        let err = format_context(false, "alice").unwrap_err();
+        assert_eq!(err.get_context("key"), Some("value"));
+
+        let err = format_code_context(false, "alice").unwrap_err();
+        assert_eq!(err.code(), Some(404));
+        assert_eq!(err.get_context("key"), Some("value"));
+        assert_eq!(err.get_context("key2"), Some("value2"));
+
+        let err = code(false).unwrap_err();
+        assert_eq!(err.code(), Some(404));
+
+        let err = context(false).unwrap_err();
+        assert_eq!(err.get_context("key"), Some("value"));
+        assert_eq!(err.get_context("key2"), Some("value2"));
+
+        let err = code_context(false).unwrap_err();
+        assert_eq!(err.code(), Some(500));
+        assert_eq!(err.get_context("table"), Some("users"));
+    }
+
+    #[test]
+    fn test_with_context_map() {
+        use std::collections::HashMap;
+
+        let mut ctx = HashMap::new();
+        ctx.insert("table".to_string(), "users".to_string());
+        ctx.insert("operation".to_string(), "insert".to_string());
+
+        let err = Error::new("Database error").with_context_map(ctx);
+        assert_eq!(err.get_context("table"), Some("users"));
+        assert_eq!(err.get_context("operation"), Some("insert"));
+    }
+
+    #[test]
+    fn test_object_safety() {
+        // Test that Error can be used as a trait object (dynamic dispatch)
+        let err = Error::new("test error").with_code(500);
+        let trait_obj: &dyn StdError = &err;
+
+        assert!(trait_obj.to_string().contains("test error"));
+
+        // Test that we can store Error in a Box<dyn Error>
+        let boxed: Box<dyn StdError + Send + Sync> = Box::new(err);
+        assert!(boxed.to_string().contains("test error"));
+    }
+
+    #[test]
+    fn test_builder_chaining() {
+        // Test complete builder pattern chain
+        let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file.txt");
+        let err = Error::new("Complex error")
+            .with_code(500)
+            .with_source(io_err)
+            .with_context_value("table", "users")
+            .with_context_value("operation", "read")
+            .with_backtrace();
+
+        assert_eq!(err.code(), Some(500));
+        assert_eq!(err.message(), "Complex error");
+        assert!(err.source().is_some());
+        assert_eq!(err.get_context("table"), Some("users"));
+        assert_eq!(err.get_context("operation"), Some("read"));
+        assert!(err.backtrace().is_some()); // Enabled via with_backtrace()
+    }
+
+    #[test]
+    fn test_location_capture() {
+        let line = line!() + 1;
+        let err = Error::new("direct");
+        let location = err.location().unwrap();
+        assert_eq!(location.file(), file!());
+        assert_eq!(location.line(), line);
+
+        let line = line!() + 1;
+        let err = error!("macro {}", 1; code: 500);
+        assert_eq!(err.location().unwrap().line(), line);
+
+        // `?` records the location of the `?` operator
+        let line = line!() + 2;
+        let fails = || -> Result<()> {
+            Err(std::io::Error::other("io"))?;
+            Ok(())
+        };
+        let err = fails().unwrap_err();
+        assert_eq!(err.location().unwrap().file(), file!());
+        assert_eq!(err.location().unwrap().line(), line);
+
+        let line = line!() + 2;
+        let bails = || -> Result<()> {
+            bail!("bailed");
+        };
+        assert_eq!(bails().unwrap_err().location().unwrap().line(), line);
+    }
+
+    #[test]
+    fn test_alternate_display_includes_location() {
+        let err = Error::new("error")
+            .with_code(500)
+            .with_context_value("k", "v");
+        let location = err.location().unwrap();
+        assert_eq!(err.to_string(), "[500] error (k: v)");
+        assert_eq!(
+            format!("{err:#}"),
+            format!(
+                "[500] error (k: v) at {}:{}:{}",
+                location.file(),
+                location.line(),
+                location.column()
+            )
+        );
+    }
+
+    #[test]
+    fn test_from_implementations() {
+        let err: Error = "string error".into();
+        assert_eq!(err.message(), "string error");
+
+        let err: Error = String::from("owned string").into();
+        assert_eq!(err.message(), "owned string");
+    }
+
+    #[test]
+    fn test_result_type() {
+        fn returns_result() -> Result<i32> {
+            Err(Error::new("failed"))
+        }
+
+        assert!(returns_result().is_err());
+    }
+}
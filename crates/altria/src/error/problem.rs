@@ -0,0 +1,435 @@
+//! RFC 9457 Problem Details rendering for [`Error`]
+//!
+//! Provides a serializable view of an [`Error`] following the
+//! `application/problem+json` format:
+//! - `type`: URI identifying the problem type, built from a configurable base
+//! - `title`: short, per-code summary of the problem type
+//! - `status`: HTTP status code (see [`Error::http_status`])
+//! - `detail`: the error message
+//! - `instance`: optional URI identifying this occurrence
+//! - Extension members drawn from the error context, with sensitive values redacted
+//!
+//! # Examples
+//!
+//! ```
+//! use altria::error::Error;
+//! use altria::error::problem::ProblemConfig;
+//!
+//! let config = ProblemConfig::new()
+//!     .with_type_base("https://example.com/problems")
+//!     .with_title(404, "Resource Not Found");
+//!
+//! let err = Error::new("User 42 does not exist")
+//!     .with_code(404)
+//!     .with_context_value("user_id", "42");
+//!
+//! let problem = config.render(&err).with_instance("/users/42");
+//! assert_eq!(problem.type_uri(), "https://example.com/problems/404");
+//! assert_eq!(problem.title(), Some("Resource Not Found"));
+//! assert_eq!(problem.status(), 404);
+//! assert_eq!(problem.detail(), "User 42 does not exist");
+//! ```
+
+use super::{Context, Error, ErrorKind};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Media type of a problem details JSON document
+pub const CONTENT_TYPE: &str = "application/problem+json";
+
+/// Default problem type URI when no type base is configured
+pub const ABOUT_BLANK: &str = "about:blank";
+
+/// Member names defined by RFC 9457, which context keys may not override
+const RESERVED_MEMBERS: &[&str] = &[
+    "type", "title", "status", "detail", "instance", "code", "kind",
+];
+
+/// A serializable RFC 9457 problem details document
+///
+/// Created by [`ProblemConfig::render`] or [`Error::to_problem`].
+/// The source chain and backtrace of the error are never included.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProblemDetails {
+    /// URI identifying the problem type
+    #[serde(rename = "type")]
+    type_uri: String,
+    /// Short, human-readable summary of the problem type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    /// HTTP status code
+    status: u16,
+    /// Human-readable explanation specific to this occurrence
+    detail: String,
+    /// URI reference identifying this specific occurrence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    /// Application-specific error code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<i64>,
+    /// Error kind
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<ErrorKind>,
+    /// Extension members drawn from the error context
+    #[serde(flatten)]
+    extensions: Context,
+    /// Suggested delay before retrying, for a `Retry-After` header
+    #[serde(skip)]
+    retry_after: Option<Duration>,
+}
+
+impl ProblemDetails {
+    /// Set the occurrence URI (builder pattern)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let problem = Error::new("Not found").with_code(404).to_problem()
+    ///     .with_instance("/orders/7");
+    /// assert_eq!(problem.instance(), Some("/orders/7"));
+    /// ```
+    #[must_use]
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Get the problem type URI
+    #[must_use]
+    pub fn type_uri(&self) -> &str {
+        &self.type_uri
+    }
+
+    /// Get the problem title
+    #[must_use]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Get the HTTP status code
+    #[must_use]
+    pub const fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Get the occurrence-specific detail
+    #[must_use]
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+
+    /// Get the occurrence URI
+    #[must_use]
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    /// Get the application-specific error code
+    #[must_use]
+    pub const fn code(&self) -> Option<i64> {
+        self.code
+    }
+
+    /// Get the error kind
+    #[must_use]
+    pub const fn kind(&self) -> Option<ErrorKind> {
+        self.kind
+    }
+
+    /// Get the extension members
+    #[must_use]
+    pub const fn extensions(&self) -> &Context {
+        &self.extensions
+    }
+
+    /// Get the suggested delay before retrying, if any
+    ///
+    /// Not part of the JSON document; HTTP integrations send it as a `Retry-After` header.
+    #[must_use]
+    pub const fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+}
+
+/// Configuration for rendering errors as [`ProblemDetails`]
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::Error;
+/// use altria::error::problem::ProblemConfig;
+///
+/// let config = ProblemConfig::new()
+///     .with_type_base("https://example.com/problems/")
+///     .with_title(409, "Conflict")
+///     .with_title(10_001, "Quota Exceeded");
+///
+/// let problem = config.render(&Error::new("Daily quota used up").with_code(10_001));
+/// assert_eq!(problem.type_uri(), "https://example.com/problems/10001");
+/// assert_eq!(problem.title(), Some("Quota Exceeded"));
+/// assert_eq!(problem.status(), 500);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProblemConfig {
+    /// Base URI for problem types, joined with the error code
+    type_base: Option<String>,
+    /// Titles keyed by error code
+    titles: HashMap<i64, String>,
+}
+
+impl ProblemConfig {
+    /// Create a configuration with no type base and no custom titles
+    ///
+    /// Problems rendered with this configuration use `about:blank` as their
+    /// type and the standard HTTP reason phrase as their title.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the base URI for problem types (builder pattern)
+    ///
+    /// Errors with a code get the type `{base}/{code}`; errors without a code
+    /// keep `about:blank`. A trailing `/` on the base is ignored.
+    #[must_use]
+    pub fn with_type_base(mut self, base: impl Into<String>) -> Self {
+        self.type_base = Some(base.into());
+        self
+    }
+
+    /// Set the title for an error code (builder pattern)
+    #[must_use]
+    pub fn with_title(mut self, code: i64, title: impl Into<String>) -> Self {
+        self.titles.insert(code, title.into());
+        self
+    }
+
+    /// Render an error as a problem details document
+    #[must_use]
+    pub fn render(&self, error: &Error) -> ProblemDetails {
+        let status = error.http_status();
+
+        let type_uri = match (&self.type_base, error.code()) {
+            (Some(base), Some(code)) => format!("{}/{code}", base.trim_end_matches('/')),
+            _ => ABOUT_BLANK.to_string(),
+        };
+
+        let title = error
+            .code()
+            .and_then(|code| self.titles.get(&code).cloned())
+            .or_else(|| reason_phrase(status).map(str::to_string));
+
+        let extensions = error
+            .context()
+            .iter_redacted()
+            .filter(|(key, _)| !RESERVED_MEMBERS.contains(key))
+            .map(|(key, value)| (key, value.into_owned()))
+            .collect();
+
+        ProblemDetails {
+            type_uri,
+            title,
+            status,
+            detail: error.message().to_string(),
+            instance: None,
+            code: error.code(),
+            kind: error.kind(),
+            extensions,
+            retry_after: error.retry_after(),
+        }
+    }
+
+    /// Render an error with its detail localized for the preferred locales
+    ///
+    /// The detail is rendered with [`Error::localized_message`], falling back to
+    /// the literal message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{set_message_catalog, Catalog, Error};
+    /// use altria::error::problem::ProblemConfig;
+    ///
+    /// set_message_catalog(Catalog::new().with_message("fr", "order.closed", "La commande {id} est close"));
+    ///
+    /// let err = Error::new("Order 7 is closed")
+    ///     .with_code(409)
+    ///     .with_message_key("order.closed")
+    ///     .with_context_value("id", 7);
+    ///
+    /// let problem = ProblemConfig::new().render_localized(&err, &["fr-BE", "en"]);
+    /// assert_eq!(problem.detail(), "La commande 7 est close");
+    /// ```
+    #[must_use]
+    pub fn render_localized<L: AsRef<str>>(&self, error: &Error, locales: &[L]) -> ProblemDetails {
+        ProblemDetails {
+            detail: error.localized_message(locales).into_owned(),
+            ..self.render(error)
+        }
+    }
+}
+
+impl Error {
+    /// Render this error as a problem details document with the default configuration
+    ///
+    /// Use [`ProblemConfig::render`] to customize type URIs and titles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let problem = Error::new("Missing field 'email'").with_code(400).to_problem();
+    /// assert_eq!(problem.type_uri(), "about:blank");
+    /// assert_eq!(problem.title(), Some("Bad Request"));
+    /// assert_eq!(problem.status(), 400);
+    /// ```
+    #[must_use]
+    pub fn to_problem(&self) -> ProblemDetails {
+        ProblemConfig::new().render(self)
+    }
+}
+
+/// Standard reason phrase for common HTTP error status codes
+const fn reason_phrase(status: u16) -> Option<&'static str> {
+    Some(match status {
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        402 => "Payment Required",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        406 => "Not Acceptable",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        410 => "Gone",
+        411 => "Length Required",
+        412 => "Precondition Failed",
+        413 => "Content Too Large",
+        414 => "URI Too Long",
+        415 => "Unsupported Media Type",
+        416 => "Range Not Satisfiable",
+        417 => "Expectation Failed",
+        421 => "Misdirected Request",
+        422 => "Unprocessable Content",
+        423 => "Locked",
+        424 => "Failed Dependency",
+        425 => "Too Early",
+        426 => "Upgrade Required",
+        428 => "Precondition Required",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        451 => "Unavailable For Legal Reasons",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        505 => "HTTP Version Not Supported",
+        507 => "Insufficient Storage",
+        508 => "Loop Detected",
+        511 => "Network Authentication Required",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_rendering() {
+        let problem = Error::new("Not here").with_code(404).to_problem();
+        assert_eq!(problem.type_uri(), ABOUT_BLANK);
+        assert_eq!(problem.title(), Some("Not Found"));
+        assert_eq!(problem.status(), 404);
+        assert_eq!(problem.detail(), "Not here");
+        assert_eq!(problem.instance(), None);
+        assert_eq!(problem.code(), Some(404));
+    }
+
+    #[test]
+    fn test_status_fallback() {
+        let problem = Error::new("boom").to_problem();
+        assert_eq!(problem.status(), 500);
+        assert_eq!(problem.title(), Some("Internal Server Error"));
+        assert_eq!(problem.code(), None);
+
+        let problem = Error::new("custom").with_code(10_001).to_problem();
+        assert_eq!(problem.status(), 500);
+        assert_eq!(problem.code(), Some(10_001));
+    }
+
+    #[test]
+    fn test_kind_drives_status() {
+        let problem = Error::new("slow")
+            .with_kind(ErrorKind::Timeout)
+            .to_problem();
+        assert_eq!(problem.status(), 504);
+        assert_eq!(problem.title(), Some("Gateway Timeout"));
+        assert_eq!(problem.kind(), Some(ErrorKind::Timeout));
+        assert_eq!(serde_json::to_value(&problem).unwrap()["kind"], "timeout");
+    }
+
+    #[test]
+    fn test_type_base_and_titles() {
+        let config = ProblemConfig::new()
+            .with_type_base("https://example.com/probs/")
+            .with_title(409, "Version Conflict");
+
+        let problem = config.render(&Error::new("stale").with_code(409));
+        assert_eq!(problem.type_uri(), "https://example.com/probs/409");
+        assert_eq!(problem.title(), Some("Version Conflict"));
+
+        // No code: type stays about:blank even with a base
+        let problem = config.render(&Error::new("boom"));
+        assert_eq!(problem.type_uri(), ABOUT_BLANK);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let err = Error::new("Out of credit")
+            .with_code(403)
+            .with_context_value("balance", 30)
+            .with_context_value("type", "ignored")
+            .with_sensitive_context("card", "4111 1111 1111 1111")
+            .with_source(std::io::Error::other("hidden"));
+
+        let problem = ProblemConfig::new()
+            .with_type_base("https://example.com/probs")
+            .with_title(403, "Insufficient Credit")
+            .render(&err)
+            .with_instance("/account/12345/msgs/abc");
+
+        let value = serde_json::to_value(&problem).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "type": "https://example.com/probs/403",
+                "title": "Insufficient Credit",
+                "status": 403,
+                "detail": "Out of credit",
+                "instance": "/account/12345/msgs/abc",
+                "code": 403,
+                "balance": 30,
+                "card": "[REDACTED]",
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_status_has_no_default_title() {
+        let problem = Error::new("teapot").with_code(418).to_problem();
+        assert_eq!(problem.status(), 418);
+        assert_eq!(problem.title(), None);
+        assert!(
+            !serde_json::to_string(&problem)
+                .unwrap()
+                .contains("\"title\"")
+        );
+    }
+}
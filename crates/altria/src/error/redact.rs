@@ -0,0 +1,128 @@
+//! Redaction of sensitive context values
+//!
+//! Context entries are sensitive when they were added with
+//! [`Error::with_sensitive_context`](super::Error::with_sensitive_context) or
+//! their key matches one of the process-wide patterns set with
+//! [`set_redacted_keys`]. Sensitive values are replaced by [`REDACTED`] in
+//! `Display`, `Debug`, serialization and HTTP renderings, while
+//! [`Context::get`](super::Context::get) still returns the raw value.
+
+use parking_lot::RwLock;
+
+/// Placeholder rendered in place of sensitive context values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Process-wide redacted key patterns
+static REDACTED_KEYS: RwLock<Vec<String>> = parking_lot::const_rwlock(Vec::new());
+
+/// Set the process-wide key patterns whose context values are always redacted
+///
+/// Patterns match keys case-insensitively, and `*` matches any sequence of
+/// characters. Replaces any previously set patterns; typically called once
+/// at startup.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{set_redacted_keys, Error};
+///
+/// set_redacted_keys(["password", "*token*", "*_secret"]);
+///
+/// let err = Error::new("Login failed")
+///     .with_context_value("user", "alice")
+///     .with_context_value("Password", "hunter2")
+///     .with_context_value("refresh_token_v2", "abc");
+///
+/// assert_eq!(
+///     err.to_string(),
+///     "Login failed (user: alice, Password: [REDACTED], refresh_token_v2: [REDACTED])"
+/// );
+/// assert_eq!(err.get_context("Password"), Some("hunter2"));
+/// ```
+pub fn set_redacted_keys<I, S>(patterns: I)
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    *REDACTED_KEYS.write() = patterns.into_iter().map(Into::into).collect();
+}
+
+/// Get the process-wide redacted key patterns
+#[must_use]
+pub fn redacted_keys() -> Vec<String> {
+    REDACTED_KEYS.read().clone()
+}
+
+/// Check whether a key matches any process-wide redacted key pattern
+pub(super) fn is_redacted_key(key: &str) -> bool {
+    REDACTED_KEYS
+        .read()
+        .iter()
+        .any(|pattern| key_matches(pattern, key))
+}
+
+/// Case-insensitive glob match where `*` matches any sequence of characters
+fn key_matches(pattern: &str, key: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let key = key.to_lowercase();
+
+    let mut parts = pattern.split('*');
+    // `split` always yields at least one part
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = key.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*`: the whole key must match
+        return rest.is_empty();
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_match() {
+        assert!(key_matches("password", "password"));
+        assert!(key_matches("password", "PassWord"));
+        assert!(!key_matches("password", "password2"));
+        assert!(!key_matches("password", "my_password"));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(key_matches("*token*", "token"));
+        assert!(key_matches("*token*", "access_token"));
+        assert!(key_matches("*token*", "Token_ID"));
+        assert!(!key_matches("*token*", "tok"));
+
+        assert!(key_matches("*_secret", "client_secret"));
+        assert!(!key_matches("*_secret", "secret"));
+        assert!(!key_matches("*_secret", "client_secret_id"));
+
+        assert!(key_matches("db.*.password", "db.primary.password"));
+        assert!(!key_matches("db.*.password", "db.primary.user"));
+        assert!(key_matches("a*b*c", "abc"));
+        assert!(key_matches("a*b*c", "a-b-b-c"));
+        assert!(!key_matches("a*b*c", "acb"));
+        assert!(key_matches("*", "anything"));
+    }
+
+    #[test]
+    fn test_overlapping_parts() {
+        // The final part must not reuse characters consumed by the prefix
+        assert!(!key_matches("ab*ba", "aba"));
+        assert!(key_matches("ab*ba", "abba"));
+    }
+}
@@ -0,0 +1,473 @@
+//! Declared application error codes
+//!
+//! The [`error_codes!`](crate::error_codes) macro declares an enum of error
+//! codes, each with a numeric value, an HTTP status, a default message and
+//! documentation. Duplicate values within one enum are rejected at compile time.
+//!
+//! An [`ErrorRegistry`] collects the codes of several enums, for example one
+//! per service, reports codes declared more than once, and renders every code
+//! as JSON (through `serde`) or as a Markdown table for API documentation.
+
+use super::Error;
+use serde::Serialize;
+use std::fmt::Write as _;
+
+/// An enum of declared application error codes
+///
+/// Implemented by [`error_codes!`](crate::error_codes); there is usually no
+/// need to implement it by hand.
+pub trait ErrorCode: Copy + Send + Sync + 'static {
+    /// Every code of the enum, in declaration order
+    const ALL: &'static [Self];
+
+    /// Get the name of the enum
+    fn group() -> &'static str;
+
+    /// Get the name of the variant
+    fn name(self) -> &'static str;
+
+    /// Get the numeric error code
+    fn code(self) -> i64;
+
+    /// Get the HTTP status errors with this code are rendered with
+    fn http_status(self) -> u16;
+
+    /// Get the default message
+    fn message(self) -> &'static str;
+
+    /// Get the lines of the variant's doc comment
+    fn doc_lines(self) -> &'static [&'static str];
+
+    /// Get the documentation as a single paragraph
+    fn description(self) -> String {
+        self.doc_lines()
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Find the variant with a numeric code
+    fn from_code(code: i64) -> Option<Self> {
+        Self::ALL.iter().copied().find(|c| c.code() == code)
+    }
+
+    /// Create an error with this code, its HTTP status and its default message
+    #[track_caller]
+    fn error(self) -> Error {
+        self.error_with_message(self.message())
+    }
+
+    /// Create an error with this code and its HTTP status, and a custom message
+    #[track_caller]
+    fn error_with_message(self, message: impl Into<String>) -> Error {
+        Error::new(message)
+            .with_code_traced(self.code())
+            .with_http_status(self.http_status())
+    }
+}
+
+/// Declare an enum of application error codes
+///
+/// Each variant has a numeric code, an HTTP status and a default message; its
+/// doc comment is the documentation exported by [`ErrorRegistry`]. The macro
+/// implements [`ErrorCode`](crate::error::ErrorCode), `Display` (the default
+/// message) and conversion into [`Error`](crate::error::Error).
+///
+/// Duplicate codes within the enum and statuses outside `400..=599` are
+/// compile-time errors.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{Error, ErrorCode};
+///
+/// altria::error_codes! {
+///     /// Error codes of the billing service
+///     pub enum BillingCode {
+///         /// The customer has no payment method on file
+///         NoPaymentMethod = 20_001 { status: 402, message: "No payment method on file" },
+///         /// The invoice was already paid
+///         AlreadyPaid = 20_002 { status: 409, message: "Invoice already paid" },
+///     }
+/// }
+///
+/// let err: Error = BillingCode::NoPaymentMethod.into();
+/// assert_eq!(err.code(), Some(20_001));
+/// assert_eq!(err.http_status(), 402);
+/// assert_eq!(err.message(), "No payment method on file");
+///
+/// let err = BillingCode::AlreadyPaid
+///     .error_with_message("Invoice 7 already paid")
+///     .with_context_value("invoice", 7);
+/// assert_eq!(err.http_status(), 409);
+///
+/// assert_eq!(BillingCode::from_code(20_002), Some(BillingCode::AlreadyPaid));
+/// ```
+#[macro_export]
+macro_rules! error_codes {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[doc = $doc:literal])*
+                $variant:ident = $code:literal {
+                    status: $status:expr,
+                    message: $message:expr $(,)?
+                }
+            ),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(i64)]
+        $vis enum $name {
+            $(
+                $(#[doc = $doc])*
+                $variant = $code,
+            )+
+        }
+
+        const _: () = {
+            $(
+                assert!(
+                    $status >= 400 && $status <= 599,
+                    concat!("HTTP status of `", stringify!($variant), "` is not an error status"),
+                );
+            )+
+        };
+
+        impl $crate::error::ErrorCode for $name {
+            const ALL: &'static [Self] = &[$(Self::$variant),+];
+
+            fn group() -> &'static str {
+                stringify!($name)
+            }
+
+            fn name(self) -> &'static str {
+                match self {
+                    $(Self::$variant => stringify!($variant),)+
+                }
+            }
+
+            fn code(self) -> i64 {
+                self as i64
+            }
+
+            fn http_status(self) -> u16 {
+                match self {
+                    $(Self::$variant => $status,)+
+                }
+            }
+
+            fn message(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $message,)+
+                }
+            }
+
+            fn doc_lines(self) -> &'static [&'static str] {
+                match self {
+                    $(Self::$variant => &[$($doc),*],)+
+                }
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str($crate::error::ErrorCode::message(*self))
+            }
+        }
+
+        impl ::core::convert::From<$name> for $crate::error::Error {
+            #[track_caller]
+            fn from(code: $name) -> Self {
+                $crate::error::ErrorCode::error(code)
+            }
+        }
+    };
+}
+
+/// The documentation of a declared error code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorCodeInfo {
+    /// Numeric error code
+    code: i64,
+    /// Name of the enum declaring the code
+    group: &'static str,
+    /// Name of the variant
+    name: &'static str,
+    /// HTTP status errors with this code are rendered with
+    status: u16,
+    /// Default message
+    message: &'static str,
+    /// Documentation, as a single paragraph
+    description: String,
+}
+
+impl ErrorCodeInfo {
+    /// Describe a declared error code
+    #[must_use]
+    pub fn new<C: ErrorCode>(code: C) -> Self {
+        Self {
+            code: code.code(),
+            group: C::group(),
+            name: code.name(),
+            status: code.http_status(),
+            message: code.message(),
+            description: code.description(),
+        }
+    }
+
+    /// Get the numeric error code
+    #[must_use]
+    pub const fn code(&self) -> i64 {
+        self.code
+    }
+
+    /// Get the name of the enum declaring the code
+    #[must_use]
+    pub const fn group(&self) -> &'static str {
+        self.group
+    }
+
+    /// Get the name of the variant
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get the HTTP status
+    #[must_use]
+    pub const fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Get the default message
+    #[must_use]
+    pub const fn message(&self) -> &'static str {
+        self.message
+    }
+
+    /// Get the documentation
+    #[must_use]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// A registry of the error codes of one or more [`ErrorCode`] enums
+///
+/// Serializes as a list of codes, sorted by numeric value.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::ErrorRegistry;
+///
+/// altria::error_codes! {
+///     pub enum UserCode {
+///         /// The user does not exist
+///         NotFound = 10_001 { status: 404, message: "User not found" },
+///     }
+/// }
+///
+/// altria::error_codes! {
+///     pub enum OrderCode {
+///         /// The order was already shipped and can no longer change
+///         Shipped = 11_001 { status: 409, message: "Order already shipped" },
+///     }
+/// }
+///
+/// let registry = ErrorRegistry::new().with::<OrderCode>().with::<UserCode>();
+/// assert!(registry.collisions().is_empty());
+///
+/// let json = serde_json::to_value(&registry).unwrap();
+/// assert_eq!(json[0]["code"], 10_001);
+/// assert_eq!(json[0]["name"], "NotFound");
+///
+/// assert!(registry.to_markdown().contains(
+///     "| 11001 | `OrderCode::Shipped` | 409 | Order already shipped | \
+///      The order was already shipped and can no longer change |"
+/// ));
+/// ```
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct ErrorRegistry {
+    /// Registered codes, sorted by numeric value
+    entries: Vec<ErrorCodeInfo>,
+}
+
+impl ErrorRegistry {
+    /// Create an empty registry
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Register every code of an enum (builder pattern)
+    #[must_use]
+    pub fn with<C: ErrorCode>(mut self) -> Self {
+        self.register::<C>();
+        self
+    }
+
+    /// Register every code of an enum
+    pub fn register<C: ErrorCode>(&mut self) {
+        self.entries
+            .extend(C::ALL.iter().copied().map(ErrorCodeInfo::new));
+        // Stable sort keeps registration order for colliding codes
+        self.entries.sort_by_key(ErrorCodeInfo::code);
+    }
+
+    /// Get the registered codes, sorted by numeric value
+    #[must_use]
+    pub fn entries(&self) -> &[ErrorCodeInfo] {
+        &self.entries
+    }
+
+    /// Get the description of a numeric code, if registered
+    #[must_use]
+    pub fn get(&self, code: i64) -> Option<&ErrorCodeInfo> {
+        self.entries.iter().find(|entry| entry.code == code)
+    }
+
+    /// Get the numeric codes registered more than once, sorted
+    #[must_use]
+    pub fn collisions(&self) -> Vec<i64> {
+        let mut collisions: Vec<i64> = self
+            .entries
+            .windows(2)
+            .filter(|pair| pair[0].code == pair[1].code)
+            .map(|pair| pair[0].code)
+            .collect();
+        collisions.dedup();
+        collisions
+    }
+
+    /// Render the registry as a Markdown table
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from(
+            "| Code | Name | HTTP status | Message | Description |\n\
+             |---:|---|---:|---|---|\n",
+        );
+        for entry in &self.entries {
+            let _ = writeln!(
+                out,
+                "| {} | `{}::{}` | {} | {} | {} |",
+                entry.code,
+                entry.group,
+                entry.name,
+                entry.status,
+                escape_cell(entry.message),
+                escape_cell(&entry.description),
+            );
+        }
+        out
+    }
+}
+
+/// Escape a Markdown table cell
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use serde_json::json;
+
+    crate::error_codes! {
+        /// Codes used in tests
+        enum TestCode {
+            /// The widget does not exist
+            ///
+            /// Check the widget id.
+            Missing = 1_001 { status: 404, message: "Widget not found" },
+            /// Widgets | gadgets are locked
+            Locked = 1_002 { status: 423, message: "Widget locked" },
+            Broken = 1_003 { status: 500, message: "Widget broken" },
+        }
+    }
+
+    crate::error_codes! {
+        enum OtherCode {
+            /// Collides with `TestCode::Locked`
+            Clash = 1_002 { status: 409, message: "Clash" },
+        }
+    }
+
+    #[test]
+    fn test_declared_codes() {
+        assert_eq!(TestCode::ALL.len(), 3);
+        assert_eq!(TestCode::group(), "TestCode");
+        assert_eq!(TestCode::Locked.name(), "Locked");
+        assert_eq!(TestCode::Locked.code(), 1_002);
+        assert_eq!(TestCode::Locked.http_status(), 423);
+        assert_eq!(TestCode::Missing.to_string(), "Widget not found");
+        assert_eq!(
+            TestCode::Missing.description(),
+            "The widget does not exist Check the widget id."
+        );
+        assert_eq!(TestCode::Broken.description(), "");
+        assert_eq!(TestCode::from_code(1_003), Some(TestCode::Broken));
+        assert_eq!(TestCode::from_code(42), None);
+    }
+
+    #[test]
+    fn test_into_error() {
+        let err: Error = TestCode::Locked.into();
+        assert_eq!(err.code(), Some(1_002));
+        assert_eq!(err.http_status(), 423);
+        assert_eq!(err.message(), "Widget locked");
+        assert!(err.location().unwrap().file().ends_with("registry.rs"));
+
+        let err = TestCode::Missing
+            .error_with_message("Widget 7 not found")
+            .with_kind(ErrorKind::NotFound);
+        assert_eq!(err.to_string(), "[1001] Widget 7 not found");
+        assert_eq!(err.http_status(), 404);
+    }
+
+    #[test]
+    fn test_registry_json() {
+        let registry = ErrorRegistry::new().with::<TestCode>();
+        assert_eq!(
+            serde_json::to_value(&registry).unwrap()[0],
+            json!({
+                "code": 1001,
+                "group": "TestCode",
+                "name": "Missing",
+                "status": 404,
+                "message": "Widget not found",
+                "description": "The widget does not exist Check the widget id.",
+            })
+        );
+        assert_eq!(registry.get(1_003).unwrap().name(), "Broken");
+        assert!(registry.get(9).is_none());
+    }
+
+    #[test]
+    fn test_registry_markdown() {
+        let markdown = ErrorRegistry::new().with::<TestCode>().to_markdown();
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[3],
+            r"| 1002 | `TestCode::Locked` | 423 | Widget locked | Widgets \| gadgets are locked |"
+        );
+    }
+
+    #[test]
+    fn test_collisions() {
+        let registry = ErrorRegistry::new().with::<TestCode>().with::<OtherCode>();
+        assert_eq!(registry.collisions(), [1_002]);
+        let names: Vec<&str> = registry.entries().iter().map(ErrorCodeInfo::name).collect();
+        assert_eq!(names, ["Missing", "Locked", "Clash", "Broken"]);
+    }
+}
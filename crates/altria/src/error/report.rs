@@ -0,0 +1,362 @@
+//! Human-readable reports of an [`Error`] and its source chain
+//!
+//! [`Display`](fmt::Display) on [`Error`] only renders the top-level error.
+//! [`Report`] renders the whole chain, either on a single line
+//! ([`ReportStyle::Compact`]) or as a multi-line tree with location and
+//! backtrace ([`ReportStyle::Pretty`]). The children of aggregate errors
+//! (see [`Error::from_many`]) are rendered in full, each with its own chain.
+
+use super::Error;
+use std::backtrace::BacktraceStatus;
+use std::error::Error as StdError;
+use std::fmt;
+
+/// ANSI escape sequences used when coloring is enabled
+mod ansi {
+    pub const RESET: &str = "\x1b[0m";
+    pub const BOLD_RED: &str = "\x1b[1;31m";
+    pub const BOLD_YELLOW: &str = "\x1b[1;33m";
+    pub const DIM: &str = "\x1b[2m";
+}
+
+/// Layout of a [`Report`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportStyle {
+    /// Single line: the error followed by each cause, separated by `: `
+    #[default]
+    Compact,
+    /// Multi-line: the error, its location, an indented "Caused by:" list,
+    /// and the span trace and backtrace when captured
+    Pretty,
+}
+
+/// Formatter for an [`Error`] and its full source chain
+///
+/// Created by [`Error::report`].
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::Error;
+///
+/// let err = Error::new("Failed to load config")
+///     .with_code(500)
+///     .with_context_value("path", "/etc/app.toml")
+///     .with_source(std::io::Error::other("permission denied"));
+///
+/// assert_eq!(
+///     err.report().to_string(),
+///     "[500] Failed to load config (path: /etc/app.toml): permission denied"
+/// );
+///
+/// let pretty = err.report().pretty().to_string();
+/// assert!(pretty.starts_with("[500] Failed to load config (path: /etc/app.toml)\n"));
+/// assert!(pretty.contains("Caused by:\n    0: permission denied"));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Report<'a> {
+    /// Error being reported
+    error: &'a Error,
+    /// Layout
+    style: ReportStyle,
+    /// Whether to emit ANSI color codes
+    color: bool,
+}
+
+impl<'a> Report<'a> {
+    /// Create a compact, uncolored report for an error
+    #[must_use]
+    pub const fn new(error: &'a Error) -> Self {
+        Self {
+            error,
+            style: ReportStyle::Compact,
+            color: false,
+        }
+    }
+
+    /// Set the layout (builder pattern)
+    #[must_use]
+    pub const fn with_style(mut self, style: ReportStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Use the single-line layout (builder pattern)
+    #[must_use]
+    pub const fn compact(self) -> Self {
+        self.with_style(ReportStyle::Compact)
+    }
+
+    /// Use the multi-line layout (builder pattern)
+    #[must_use]
+    pub const fn pretty(self) -> Self {
+        self.with_style(ReportStyle::Pretty)
+    }
+
+    /// Enable or disable ANSI coloring for terminals (builder pattern)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("boom");
+    /// assert!(err.report().pretty().with_color(true).to_string().contains("\x1b["));
+    /// ```
+    #[must_use]
+    pub const fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Get the layout
+    #[must_use]
+    pub const fn style(&self) -> ReportStyle {
+        self.style
+    }
+
+    /// Check whether ANSI coloring is enabled
+    #[must_use]
+    pub const fn is_colored(&self) -> bool {
+        self.color
+    }
+
+    /// Get the ANSI sequence for a style, or nothing if coloring is disabled
+    const fn paint(&self, code: &'static str) -> &'static str {
+        if self.color { code } else { "" }
+    }
+
+    fn fmt_compact(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (red, reset) = (self.paint(ansi::BOLD_RED), self.paint(ansi::RESET));
+        write!(f, "{red}")?;
+        self.error.fmt_header(f)?;
+        write!(f, "{reset}")?;
+
+        if let Some(set) = self.error.error_set() {
+            f.write_str(": ")?;
+            for (i, child) in set.iter().enumerate() {
+                if i > 0 {
+                    f.write_str("; ")?;
+                }
+                write!(f, "{}", child.report().with_color(self.color))?;
+            }
+            return Ok(());
+        }
+
+        let mut source = self.error.source.as_deref().map(|e| e as &dyn StdError);
+        while let Some(cause) = source {
+            // Nested errors render their own chain, including aggregate children
+            if let Some(error) = cause.downcast_ref::<Error>() {
+                return write!(f, ": {}", error.report().with_color(self.color));
+            }
+            write!(f, ": {cause}")?;
+            source = cause.source();
+        }
+        Ok(())
+    }
+
+    fn fmt_pretty(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (red, yellow, dim, reset) = (
+            self.paint(ansi::BOLD_RED),
+            self.paint(ansi::BOLD_YELLOW),
+            self.paint(ansi::DIM),
+            self.paint(ansi::RESET),
+        );
+
+        write!(f, "{red}")?;
+        self.error.fmt_header(f)?;
+        write!(f, "{reset}")?;
+        if let Some(location) = self.error.location() {
+            write!(f, "\n{dim}  at {location}{reset}")?;
+        }
+
+        if let Some(set) = self.error.error_set() {
+            write!(f, "\n\n{yellow}Errors:{reset}")?;
+            for (index, child) in set.iter().enumerate() {
+                let rendered = child.report().pretty().with_color(self.color).to_string();
+                let mut lines = rendered.lines();
+                write!(f, "\n    {index}: {}", lines.next().unwrap_or_default())?;
+                for line in lines {
+                    if line.is_empty() {
+                        f.write_str("\n")?;
+                    } else {
+                        write!(f, "\n       {line}")?;
+                    }
+                }
+            }
+        } else if self.error.source.is_some() {
+            write!(f, "\n\n{yellow}Caused by:{reset}")?;
+            for (index, cause) in self.error.iter_error_chain().skip(1).enumerate() {
+                write!(f, "\n    {index}: {cause}")?;
+                if let Some(location) = cause.downcast_ref::<Error>().and_then(Error::location) {
+                    write!(f, "\n{dim}       at {location}{reset}")?;
+                }
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        if let Some(span_trace) = self.error.span_trace() {
+            write!(f, "\n\n{yellow}Span trace:{reset}\n{span_trace}")?;
+        }
+
+        if let Some(backtrace) = self.error.backtrace()
+            && backtrace.status() == BacktraceStatus::Captured
+        {
+            write!(f, "\n\n{yellow}Backtrace:{reset}\n{backtrace}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            ReportStyle::Compact => self.fmt_compact(f),
+            ReportStyle::Pretty => self.fmt_pretty(f),
+        }
+    }
+}
+
+impl Error {
+    /// Create a report of this error and its full source chain
+    ///
+    /// The report is compact and uncolored by default; see [`Report`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    ///
+    /// let err = Error::new("Request failed")
+    ///     .with_source(Error::new("Connection refused").with_code(503));
+    ///
+    /// println!("{}", err.report().pretty());
+    /// ```
+    #[must_use]
+    pub const fn report(&self) -> Report<'_> {
+        Report::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    fn nested() -> Error {
+        Error::new("Request failed")
+            .with_code(500)
+            .with_context_value("user_id", 42)
+            .with_source(
+                Error::new("Query failed")
+                    .with_code(503)
+                    .with_source(io::Error::other("connection reset")),
+            )
+    }
+
+    #[test]
+    fn test_compact() {
+        assert_eq!(
+            nested().report().to_string(),
+            "[500] Request failed (user_id: 42): [503] Query failed: connection reset"
+        );
+        assert_eq!(Error::new("alone").report().to_string(), "alone");
+    }
+
+    #[test]
+    fn test_pretty() {
+        let err = nested();
+        let report = err.report().pretty().to_string();
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines[0], "[500] Request failed (user_id: 42)");
+        assert!(lines[1].starts_with("  at "));
+        assert!(lines[1].contains("report.rs"));
+        assert_eq!(lines[2], "");
+        assert_eq!(lines[3], "Caused by:");
+        assert_eq!(lines[4], "    0: [503] Query failed");
+        assert!(lines[5].starts_with("       at "));
+        assert_eq!(lines[6], "    1: connection reset");
+        assert_eq!(lines.len(), 7);
+    }
+
+    #[test]
+    fn test_pretty_without_causes() {
+        let report = Error::new("alone").report().pretty().to_string();
+        assert!(report.starts_with("alone\n  at "));
+        assert!(!report.contains("Caused by:"));
+        assert!(!report.contains("Backtrace:"));
+    }
+
+    #[test]
+    fn test_pretty_backtrace() {
+        let report = Error::new("traced")
+            .with_backtrace()
+            .report()
+            .pretty()
+            .to_string();
+        assert!(report.contains("\n\nBacktrace:\n"));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let err = Error::from_many([
+            nested(),
+            Error::new("Cache miss").with_source(io::Error::other("evicted")),
+        ])
+        .unwrap();
+        assert_eq!(
+            err.report().to_string(),
+            "[500] 2 errors occurred: [500] Request failed (user_id: 42): [503] Query failed: \
+             connection reset; Cache miss: evicted"
+        );
+
+        let report = err.report().pretty().to_string();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "[500] 2 errors occurred");
+        assert_eq!(lines[3], "Errors:");
+        assert_eq!(lines[4], "    0: [500] Request failed (user_id: 42)");
+        assert!(lines[5].starts_with("         at "));
+        assert_eq!(lines[6], "");
+        assert_eq!(lines[7], "       Caused by:");
+        assert_eq!(lines[8], "           0: [503] Query failed");
+        assert_eq!(lines[10], "           1: connection reset");
+        assert_eq!(lines[11], "    1: Cache miss");
+        assert_eq!(lines[14], "       Caused by:");
+        assert_eq!(lines[15], "           0: evicted");
+        assert_eq!(lines.len(), 16);
+    }
+
+    #[test]
+    fn test_color() {
+        let err = nested();
+        let plain = err.report().pretty();
+        assert!(!plain.is_colored());
+        assert!(!plain.to_string().contains('\x1b'));
+
+        let colored = plain.with_color(true).to_string();
+        assert!(colored.starts_with("\x1b[1;31m[500] Request failed"));
+        assert!(colored.contains("\x1b[1;33mCaused by:\x1b[0m"));
+
+        // Nested errors and aggregate children keep the parent's coloring
+        let compact = err.report().with_color(true).to_string();
+        assert!(compact.ends_with(": \x1b[1;31m[503] Query failed\x1b[0m: connection reset"));
+
+        let set = Error::from_many([nested(), Error::new("Cache miss")]).unwrap();
+        let compact = set.report().with_color(true).to_string();
+        assert!(compact.contains("; \x1b[1;31mCache miss\x1b[0m"));
+        assert!(!set.report().to_string().contains('\x1b'));
+    }
+
+    #[test]
+    fn test_style() {
+        let err = Error::new("e");
+        assert_eq!(err.report().style(), ReportStyle::Compact);
+        assert_eq!(err.report().pretty().style(), ReportStyle::Pretty);
+        assert_eq!(
+            err.report().pretty().compact().style(),
+            ReportStyle::Compact
+        );
+    }
+}
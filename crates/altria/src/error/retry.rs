@@ -0,0 +1,311 @@
+//! Retry classification for [`Error`]
+//!
+//! Every error is either [`Retryability::Transient`] (retrying the same operation
+//! may succeed) or [`Retryability::Permanent`]. The classification can be set
+//! explicitly or derived from the error itself; see [`Error::retryability`].
+
+use super::{Error, ErrorKind, ErrorSet};
+use std::error::Error as StdError;
+use std::io;
+use std::time::Duration;
+
+/// Whether retrying an operation that failed with an [`Error`] may succeed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Retryability {
+    /// The failure is temporary; the operation may succeed if retried
+    Transient,
+    /// The failure will recur; the operation should not be retried
+    Permanent,
+}
+
+impl ErrorKind {
+    /// Get the default retry classification for this kind
+    #[must_use]
+    pub const fn retryability(self) -> Retryability {
+        match self {
+            Self::Unavailable | Self::Timeout | Self::RateLimited => Retryability::Transient,
+            _ => Retryability::Permanent,
+        }
+    }
+}
+
+impl Error {
+    /// Set the retry classification explicitly (builder pattern)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{Error, ErrorKind, Retryability};
+    ///
+    /// // Timeouts are transient by default, but this one is deterministic
+    /// let err = Error::new("Query exceeds the statement timeout")
+    ///     .with_kind(ErrorKind::Timeout)
+    ///     .with_retryability(Retryability::Permanent);
+    /// assert!(err.is_permanent());
+    /// ```
+    #[must_use]
+    pub const fn with_retryability(mut self, retryability: Retryability) -> Self {
+        self.retryability = Some(retryability);
+        self
+    }
+
+    /// Mark the error as transient with a delay before retrying (builder pattern)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{Error, ErrorKind};
+    /// use std::time::Duration;
+    ///
+    /// let err = Error::new("Too many requests")
+    ///     .with_kind(ErrorKind::RateLimited)
+    ///     .with_retry_after(Duration::from_secs(30));
+    ///
+    /// assert!(err.is_transient());
+    /// assert_eq!(err.retry_after(), Some(Duration::from_secs(30)));
+    /// ```
+    #[must_use]
+    pub fn with_retry_after(mut self, delay: Duration) -> Self {
+        self.extra_mut().retry_after = Some(delay);
+        self.retryability = Some(Retryability::Transient);
+        self
+    }
+
+    /// Get the suggested delay before retrying, if any
+    #[must_use]
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.extra.as_ref()?.retry_after
+    }
+
+    /// Get the retry classification, explicit or derived
+    ///
+    /// Resolution order:
+    /// 1. The explicit classification; [`with_retry_after`](Self::with_retry_after) implies transient
+    /// 2. For aggregate errors (see [`Error::from_many`]), the children combined:
+    ///    transient only if every child is transient
+    /// 3. The error kind (`Unavailable`, `Timeout` and `RateLimited` are transient)
+    /// 4. The error code, if it is an HTTP error status (`408`, `425`, `429`, `502`,
+    ///    `503` and `504` are transient)
+    /// 5. The source chain: transient I/O errors such as `TimedOut` or
+    ///    `ConnectionReset`, or nested errors classified by the rules above
+    /// 6. [`Retryability::Permanent`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::{Error, ErrorKind, Retryability};
+    /// use std::io;
+    ///
+    /// assert_eq!(Error::new("a").with_code(503).retryability(), Retryability::Transient);
+    /// assert_eq!(Error::new("b").with_kind(ErrorKind::NotFound).retryability(), Retryability::Permanent);
+    ///
+    /// let err = Error::new("Upstream call failed")
+    ///     .with_source(io::Error::from(io::ErrorKind::ConnectionReset));
+    /// assert_eq!(err.retryability(), Retryability::Transient);
+    ///
+    /// assert_eq!(Error::new("unknown").retryability(), Retryability::Permanent);
+    /// ```
+    #[must_use]
+    pub fn retryability(&self) -> Retryability {
+        self.classify().unwrap_or(Retryability::Permanent)
+    }
+
+    /// Check whether retrying may succeed
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        self.retryability() == Retryability::Transient
+    }
+
+    /// Check whether retrying will not help
+    #[must_use]
+    pub fn is_permanent(&self) -> bool {
+        self.retryability() == Retryability::Permanent
+    }
+
+    /// Classification from this error and its sources, if any rule applies
+    fn classify(&self) -> Option<Retryability> {
+        if let Some(retryability) = self.retryability {
+            return Some(retryability);
+        }
+        // The kind and code of an aggregate only reflect its severest child
+        if let Some(set) = self.error_set() {
+            return set_retryability(set);
+        }
+        self.kind
+            .map(ErrorKind::retryability)
+            .or_else(|| self.code.and_then(code_retryability))
+            .or_else(|| sources_retryability(self.source.as_deref()?))
+    }
+}
+
+/// Classification implied by a source chain: the first nested [`Error`] or
+/// [`ErrorSet`] decides, and transient I/O errors are recognized on the way
+fn sources_retryability(source: &(dyn StdError + 'static)) -> Option<Retryability> {
+    let mut source = Some(source);
+    while let Some(cause) = source {
+        if let Some(err) = cause.downcast_ref::<Error>() {
+            return err.classify();
+        }
+        if let Some(set) = cause.downcast_ref::<ErrorSet>() {
+            return set_retryability(set);
+        }
+        if let Some(retryability) = cause
+            .downcast_ref::<io::Error>()
+            .and_then(|err| io_retryability(err.kind()))
+        {
+            return Some(retryability);
+        }
+        source = cause.source();
+    }
+    None
+}
+
+/// Combined classification of independent errors: any permanent failure makes
+/// retrying the whole operation pointless
+fn set_retryability(set: &ErrorSet) -> Option<Retryability> {
+    if set.is_empty() {
+        return None;
+    }
+    if set.iter().all(Error::is_transient) {
+        Some(Retryability::Transient)
+    } else {
+        Some(Retryability::Permanent)
+    }
+}
+
+/// Classification implied by an HTTP error status code
+const fn code_retryability(code: i64) -> Option<Retryability> {
+    match code {
+        408 | 425 | 429 | 502..=504 => Some(Retryability::Transient),
+        400..=599 => Some(Retryability::Permanent),
+        _ => None,
+    }
+}
+
+/// Classification implied by an I/O error kind, if it is known to be transient
+fn io_retryability(kind: io::ErrorKind) -> Option<Retryability> {
+    matches!(
+        kind,
+        io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+    )
+    .then_some(Retryability::Transient)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_permanent() {
+        let err = Error::new("plain");
+        assert_eq!(err.retryability(), Retryability::Permanent);
+        assert!(err.is_permanent());
+        assert_eq!(err.retry_after(), None);
+    }
+
+    #[test]
+    fn test_kind_defaults() {
+        for kind in [
+            ErrorKind::Unavailable,
+            ErrorKind::Timeout,
+            ErrorKind::RateLimited,
+        ] {
+            assert!(Error::new("e").with_kind(kind).is_transient(), "{kind}");
+        }
+        for kind in [
+            ErrorKind::NotFound,
+            ErrorKind::InvalidInput,
+            ErrorKind::Internal,
+        ] {
+            assert!(Error::new("e").with_kind(kind).is_permanent(), "{kind}");
+        }
+    }
+
+    #[test]
+    fn test_code_defaults() {
+        for code in [408, 425, 429, 502, 503, 504] {
+            assert!(Error::new("e").with_code(code).is_transient(), "{code}");
+        }
+        for code in [400, 404, 409, 500, 501, 10_001] {
+            assert!(Error::new("e").with_code(code).is_permanent(), "{code}");
+        }
+
+        // Kind takes precedence over code
+        let err = Error::new("e")
+            .with_code(503)
+            .with_kind(ErrorKind::Forbidden);
+        assert!(err.is_permanent());
+    }
+
+    #[test]
+    fn test_io_sources() {
+        let err: Error = io::Error::from(io::ErrorKind::TimedOut).into();
+        assert!(err.is_transient());
+
+        let err = Error::new("wrapped").with_source(io::Error::from(io::ErrorKind::Interrupted));
+        assert!(err.is_transient());
+
+        let err = Error::new("wrapped").with_source(io::Error::other("disk full"));
+        assert!(err.is_permanent());
+    }
+
+    #[test]
+    fn test_nested_errors() {
+        let inner = Error::new("backend down").with_code(503);
+        let outer = Error::new("request failed").with_source(inner);
+        assert!(outer.is_transient());
+
+        // The outer error's own classification wins
+        let inner = Error::new("backend down").with_code(503);
+        let outer = Error::new("bad request").with_code(400).with_source(inner);
+        assert!(outer.is_permanent());
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let transient = || Error::new("backend down").with_code(503);
+        let permanent = || Error::new("bad request").with_code(400);
+
+        // The 503 is the severest child, but the 400 will fail again
+        let err = Error::from_many([transient(), permanent()]).unwrap();
+        assert_eq!(err.code(), Some(503));
+        assert!(err.is_permanent());
+
+        let err = Error::from_many([
+            transient(),
+            Error::new("reset").with_source(io::Error::from(io::ErrorKind::ConnectionReset)),
+        ])
+        .unwrap();
+        assert!(err.is_transient());
+
+        // Nested aggregates are combined too, and explicit classification wins
+        let nested = Error::new("batch failed")
+            .with_source(Error::from_many([transient(), permanent()]).unwrap());
+        assert!(nested.is_permanent());
+        let err = Error::from_many([transient(), permanent()])
+            .unwrap()
+            .with_retryability(Retryability::Transient);
+        assert!(err.is_transient());
+    }
+
+    #[test]
+    fn test_explicit_overrides() {
+        let err = Error::new("e")
+            .with_kind(ErrorKind::Unavailable)
+            .with_retryability(Retryability::Permanent);
+        assert!(err.is_permanent());
+
+        let err = Error::new("e")
+            .with_code(404)
+            .with_retry_after(Duration::from_millis(1500));
+        assert!(err.is_transient());
+        assert_eq!(err.retry_after(), Some(Duration::from_millis(1500)));
+    }
+}
@@ -0,0 +1,401 @@
+//! `tracing` integration for [`Error`]
+//!
+//! Available with the `tracing` feature:
+//! - [`Error::emit`] records an error as a structured `tracing` event
+//! - Every error captures the current [`SpanTrace`] when it is created, so it
+//!   remembers the request span it was raised in. Capturing requires
+//!   [`tracing_error::ErrorLayer`] to be installed in the subscriber.
+
+use super::{ContextValue, Error, ErrorKind};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use tracing::field::{FieldSet, Value, debug, display};
+use tracing::level_filters::{LevelFilter, STATIC_MAX_LEVEL};
+use tracing::{Event, Level, Metadata};
+use tracing_core::callsite::{self, Callsite};
+use tracing_core::metadata::Kind;
+use tracing_core::subscriber::Interest;
+use tracing_error::{SpanTrace, SpanTraceStatus};
+
+/// Fields of every error event, followed by one field per context key
+const FIELD_NAMES: [&str; 6] = [
+    "message",
+    "error.code",
+    "error.kind",
+    "error.sources",
+    "error.location",
+    "error.span_trace",
+];
+
+/// Prefix of the field names carrying context entries
+const CONTEXT_FIELD_PREFIX: &str = "error.context.";
+
+/// Event callsites, by level and context keys
+static CALLSITES: Mutex<BTreeMap<(Level, Vec<String>), &'static ErrorCallsite>> =
+    parking_lot::const_mutex(BTreeMap::new());
+
+/// Capture the current span trace, if an [`ErrorLayer`](tracing_error::ErrorLayer) is installed
+pub(super) fn capture_span_trace() -> Option<Box<SpanTrace>> {
+    let span_trace = SpanTrace::capture();
+    (span_trace.status() == SpanTraceStatus::CAPTURED).then(|| Box::new(span_trace))
+}
+
+/// Callsite of error events with a given level and context keys
+///
+/// `tracing` field names are usually fixed by the `event!` macro at compile
+/// time. Context keys are only known at runtime, so a callsite is built and
+/// registered the first time a level and set of keys is emitted, and lives for
+/// the rest of the program.
+struct ErrorCallsite {
+    metadata: OnceLock<Metadata<'static>>,
+}
+
+impl ErrorCallsite {
+    /// Get the metadata of events with a level and context keys
+    fn metadata<'a>(
+        level: Level,
+        keys: impl Iterator<Item = &'a str>,
+    ) -> &'static Metadata<'static> {
+        let keys: Vec<String> = keys.map(str::to_string).collect();
+        let mut created = false;
+        let callsite =
+            *CALLSITES
+                .lock()
+                .entry((level, keys))
+                .or_insert_with_key(|(level, keys)| {
+                    created = true;
+                    Self::leak(*level, keys)
+                });
+        if created {
+            callsite::register(callsite);
+        }
+        callsite.metadata()
+    }
+
+    /// Build a callsite that lives for the rest of the program
+    fn leak(level: Level, keys: &[String]) -> &'static Self {
+        let names: Vec<&'static str> =
+            FIELD_NAMES
+                .into_iter()
+                .chain(keys.iter().map(|key| {
+                    &*Box::leak(format!("{CONTEXT_FIELD_PREFIX}{key}").into_boxed_str())
+                }))
+                .collect();
+        let callsite: &'static Self = Box::leak(Box::new(Self {
+            metadata: OnceLock::new(),
+        }));
+        callsite.metadata.get_or_init(|| {
+            Metadata::new(
+                concat!("event ", file!(), ":", line!()),
+                module_path!(),
+                level,
+                Some(file!()),
+                Some(line!()),
+                Some(module_path!()),
+                FieldSet::new(names.leak(), tracing_core::identify_callsite!(callsite)),
+                Kind::EVENT,
+            )
+        });
+        callsite
+    }
+}
+
+impl Callsite for ErrorCallsite {
+    // Interest is not cached: `emit` asks the dispatcher on every event
+    fn set_interest(&self, _interest: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.metadata
+            .get()
+            .expect("callsite metadata is set before registration")
+    }
+}
+
+/// Get the field value of a context entry, `None` for null values
+fn context_value<'a>(value: &'a ContextValue, displayed: &'a dyn Value) -> Option<&'a dyn Value> {
+    match value {
+        ContextValue::Null => None,
+        ContextValue::Bool(b) => Some(b),
+        ContextValue::Int(i) => Some(i),
+        ContextValue::Float(x) => Some(x),
+        ContextValue::String(s) => Some(s),
+        ContextValue::List(_) | ContextValue::Map(_) => Some(displayed),
+    }
+}
+
+impl Error {
+    /// Emit this error as a structured `tracing` event
+    ///
+    /// The event message is the error message, with these fields:
+    /// - `error.code`: the error code, if set
+    /// - `error.kind`: the error kind, if set
+    /// - `error.sources`: the messages of the source chain, if any
+    /// - `error.location`: the call-site location, if known
+    /// - `error.span_trace`: the span trace captured at creation, if any
+    /// - `error.context.{key}`: one field per context entry, with sensitive
+    ///   values redacted. Booleans, integers, floats and strings are recorded
+    ///   as such, lists and maps with their `Display` form, and null values are
+    ///   left out.
+    ///
+    /// The event callsite of each level and set of context keys is registered
+    /// once and kept for the rest of the program, so context keys should come
+    /// from a bounded set, as literal keys do.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use tracing::Level;
+    ///
+    /// let err = Error::new("Payment declined")
+    ///     .with_code(402)
+    ///     .with_context_value("order_id", 7);
+    ///
+    /// err.emit(Level::WARN);
+    /// ```
+    pub fn emit(&self, level: Level) {
+        if level > STATIC_MAX_LEVEL || level > LevelFilter::current() {
+            return;
+        }
+        let context: Vec<_> = self.context.iter_redacted().collect();
+        let metadata = ErrorCallsite::metadata(level, context.iter().map(|(key, _)| *key));
+
+        let message = display(&self.message);
+        let kind = self.kind.map(ErrorKind::as_str);
+        let sources = self.sources_field().map(debug);
+        let location = self.location.map(display);
+        let span_trace = self.span_trace().map(display);
+        let displayed: Vec<_> = context.iter().map(|(_, value)| display(value)).collect();
+
+        let mut values: Vec<Option<&dyn Value>> = vec![
+            Some(&message),
+            self.code.as_ref().map(|code| code as &dyn Value),
+            kind.as_ref().map(|kind| kind as &dyn Value),
+            sources.as_ref().map(|sources| sources as &dyn Value),
+            location.as_ref().map(|location| location as &dyn Value),
+            span_trace
+                .as_ref()
+                .map(|span_trace| span_trace as &dyn Value),
+        ];
+        values.extend(
+            context
+                .iter()
+                .zip(&displayed)
+                .map(|((_, value), displayed)| context_value(value, displayed)),
+        );
+        // The values line up with the fields of the callsite, as the macros do
+        let fields = metadata.fields().value_set_all(&values);
+
+        tracing::dispatcher::get_default(|dispatch| {
+            if dispatch.enabled(metadata) {
+                dispatch.event(&Event::new(metadata, &fields));
+            }
+        });
+    }
+
+    /// Get the span trace captured when this error was created
+    ///
+    /// Returns `None` if no [`ErrorLayer`](tracing_error::ErrorLayer) was
+    /// installed or the error was created outside of any span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::error::Error;
+    /// use tracing_error::ErrorLayer;
+    /// use tracing_subscriber::prelude::*;
+    ///
+    /// let subscriber = tracing_subscriber::registry().with(ErrorLayer::default());
+    /// tracing::subscriber::with_default(subscriber, || {
+    ///     let span = tracing::info_span!("handle_request", path = "/users/42");
+    ///     let _guard = span.enter();
+    ///
+    ///     let err = Error::new("User not found");
+    ///     assert!(err.span_trace().unwrap().to_string().contains("handle_request"));
+    /// });
+    /// ```
+    #[must_use]
+    pub fn span_trace(&self) -> Option<&SpanTrace> {
+        self.span_trace.as_deref()
+    }
+
+    /// Collect the source chain messages for the `error.sources` field
+    fn sources_field(&self) -> Option<Vec<String>> {
+        let sources: Vec<String> = self
+            .iter_error_chain()
+            .skip(1)
+            .map(ToString::to_string)
+            .collect();
+        (!sources.is_empty()).then_some(sources)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::Arc;
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Subscriber};
+    use tracing_error::ErrorLayer;
+    use tracing_subscriber::layer::{Context as LayerContext, Layer};
+    use tracing_subscriber::prelude::*;
+
+    /// Recorded events: level and field values rendered with `Debug`, so that
+    /// strings are quoted and other types are not
+    type Recorded = Arc<Mutex<Vec<(Level, HashMap<String, String>)>>>;
+
+    struct Recorder(Recorded);
+
+    struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Recorder {
+        fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+            let mut fields = HashMap::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            self.0.lock().push((*event.metadata().level(), fields));
+        }
+    }
+
+    fn record(f: impl FnOnce()) -> Vec<(Level, HashMap<String, String>)> {
+        let recorded = Recorded::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(ErrorLayer::default())
+            .with(Recorder(recorded.clone()));
+        tracing::subscriber::with_default(subscriber, f);
+        recorded.lock().clone()
+    }
+
+    #[test]
+    fn test_emit_fields() {
+        let events = record(|| {
+            Error::new("Query failed")
+                .with_code(503)
+                .with_kind(ErrorKind::Unavailable)
+                .with_context_value("table", "users")
+                .with_context_value("attempt", 3)
+                .with_source(std::io::Error::other("connection reset"))
+                .emit(Level::ERROR);
+        });
+
+        assert_eq!(events.len(), 1);
+        let (level, fields) = &events[0];
+        assert_eq!(*level, Level::ERROR);
+        assert_eq!(fields["message"], "Query failed");
+        assert_eq!(fields["error.code"], "503");
+        assert_eq!(fields["error.kind"], r#""unavailable""#);
+        assert_eq!(fields["error.context.table"], r#""users""#);
+        assert_eq!(fields["error.context.attempt"], "3");
+        assert_eq!(fields["error.sources"], r#"["connection reset"]"#);
+        assert!(fields["error.location"].contains("trace.rs"));
+    }
+
+    #[test]
+    fn test_emit_context_field_per_key() {
+        let events = record(|| {
+            let err = Error::new("Login failed")
+                .with_context_value("user_id", 42)
+                .with_context_value("admin", false)
+                .with_context_value("ratio", 0.5)
+                .with_context_value("roles", vec!["a", "b"])
+                .with_context_value("nothing", ContextValue::Null)
+                .with_sensitive_context("password", "hunter2");
+            err.emit(Level::WARN);
+            // The registered callsite is reused
+            err.emit(Level::WARN);
+        });
+
+        assert_eq!(events.len(), 2);
+        let fields = &events[0].1;
+        assert_eq!(fields["error.context.user_id"], "42");
+        assert_eq!(fields["error.context.admin"], "false");
+        assert_eq!(fields["error.context.ratio"], "0.5");
+        assert_eq!(fields["error.context.roles"], "[a, b]");
+        assert_eq!(fields["error.context.password"], r#""[REDACTED]""#);
+        assert!(!fields.contains_key("error.context.nothing"));
+        assert_eq!(events[1].1, *fields);
+    }
+
+    #[test]
+    fn test_emit_respects_filters() {
+        let recorded = Recorded::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(Recorder(recorded.clone()).with_filter(LevelFilter::WARN));
+        tracing::subscriber::with_default(subscriber, || {
+            let err = Error::new("filtered").with_context_value("key", 1);
+            err.emit(Level::INFO);
+            err.emit(Level::ERROR);
+        });
+        let levels: Vec<Level> = recorded.lock().iter().map(|(level, _)| *level).collect();
+        assert_eq!(levels, [Level::ERROR]);
+    }
+
+    #[test]
+    fn test_emit_skips_missing_fields() {
+        let events = record(|| Error::new("plain").emit(Level::INFO));
+
+        let (level, fields) = &events[0];
+        assert_eq!(*level, Level::INFO);
+        assert_eq!(fields["message"], "plain");
+        for absent in [
+            "error.code",
+            "error.kind",
+            "error.sources",
+            "error.span_trace",
+        ] {
+            assert!(!fields.contains_key(absent), "{absent} should be absent");
+        }
+        assert!(
+            !fields
+                .keys()
+                .any(|key| key.starts_with(CONTEXT_FIELD_PREFIX))
+        );
+    }
+
+    #[test]
+    fn test_emit_levels() {
+        let levels = [
+            Level::ERROR,
+            Level::WARN,
+            Level::INFO,
+            Level::DEBUG,
+            Level::TRACE,
+        ];
+        let events = record(|| {
+            let err = Error::new("e");
+            for level in levels {
+                err.emit(level);
+            }
+        });
+        let recorded: Vec<Level> = events.iter().map(|(level, _)| *level).collect();
+        assert_eq!(recorded, levels);
+    }
+
+    #[test]
+    fn test_span_trace_capture() {
+        let events = record(|| {
+            let span = tracing::info_span!("handle_request", user_id = 42);
+            let _guard = span.enter();
+
+            let err = Error::new("inside span");
+            let span_trace = err.span_trace().expect("span trace captured");
+            assert!(span_trace.to_string().contains("handle_request"));
+            err.emit(Level::ERROR);
+        });
+        assert!(events[0].1["error.span_trace"].contains("handle_request"));
+
+        // Outside of any span, or without an ErrorLayer, nothing is captured
+        record(|| assert!(Error::new("outside").span_trace().is_none()));
+        assert!(Error::new("no subscriber").span_trace().is_none());
+    }
+}
@@ -0,0 +1,447 @@
+//! Field-level validation errors
+//!
+//! [`ValidationErrors`] collects every [`Violation`] found in an input so they
+//! can be reported at once. Converting it into an [`Error`] produces a
+//! `422 Unprocessable Content` error (configurable with
+//! [`ValidationErrors::with_code`]) whose context holds the violations as a
+//! structured list under [`ValidationErrors::CONTEXT_KEY`], so they appear in
+//! serialized errors, problem details and HTTP responses.
+
+use super::{ContextValue, Error, ErrorKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt;
+
+/// A single validation failure for one input field
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::Violation;
+///
+/// let violation = Violation::new("age", "min", "must be at least 18")
+///     .with_param("min", 18)
+///     .with_param("actual", 16);
+///
+/// assert_eq!(violation.field(), "age");
+/// assert_eq!(violation.to_string(), "age: must be at least 18");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    /// Path of the offending field, e.g. `email` or `items[2].quantity`
+    field: String,
+    /// Machine-readable violation code, e.g. `invalid_email`
+    code: String,
+    /// Human-readable description
+    message: String,
+    /// Parameters of the violated rule, e.g. `min: 18`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    params: BTreeMap<String, ContextValue>,
+}
+
+impl Violation {
+    /// Create a violation for a field
+    #[must_use]
+    pub fn new(
+        field: impl Into<String>,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Add a rule parameter (builder pattern)
+    #[must_use]
+    pub fn with_param(mut self, key: impl Into<String>, value: impl Into<ContextValue>) -> Self {
+        self.params.insert(key.into(), value.into());
+        self
+    }
+
+    /// Get the field path
+    #[must_use]
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Get the violation code
+    #[must_use]
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Get the violation message
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Get the rule parameters
+    #[must_use]
+    pub const fn params(&self) -> &BTreeMap<String, ContextValue> {
+        &self.params
+    }
+
+    /// Get a rule parameter by key
+    #[must_use]
+    pub fn param(&self, key: &str) -> Option<&ContextValue> {
+        self.params.get(key)
+    }
+
+    /// Rebuild a violation from its context representation
+    fn from_context_value(value: &ContextValue) -> Option<Self> {
+        let map = value.as_map()?;
+        let text = |key: &str| map.get(key).and_then(ContextValue::as_str);
+        Some(Self {
+            field: text("field")?.to_string(),
+            code: text("code")?.to_string(),
+            message: text("message")?.to_string(),
+            params: map
+                .get("params")
+                .and_then(ContextValue::as_map)
+                .cloned()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl From<Violation> for ContextValue {
+    fn from(violation: Violation) -> Self {
+        let mut map = BTreeMap::new();
+        map.insert("field".to_string(), Self::String(violation.field));
+        map.insert("code".to_string(), Self::String(violation.code));
+        map.insert("message".to_string(), Self::String(violation.message));
+        if !violation.params.is_empty() {
+            map.insert("params".to_string(), Self::Map(violation.params));
+        }
+        Self::Map(map)
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// A collection of validation failures
+///
+/// Serializes as a list of violations.
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{Error, ErrorKind, ValidationErrors, Violation};
+///
+/// fn validate(email: &str, age: i64) -> Result<(), Error> {
+///     let mut errors = ValidationErrors::new();
+///     if !email.contains('@') {
+///         errors.add("email", "invalid_email", "must be a valid email address");
+///     }
+///     if age < 18 {
+///         errors.push(Violation::new("age", "min", "must be at least 18").with_param("min", 18));
+///     }
+///     errors.into_result()
+/// }
+///
+/// assert!(validate("alice@example.com", 30).is_ok());
+///
+/// let err = validate("alice", 16).unwrap_err();
+/// assert_eq!(err.code(), Some(422));
+/// assert!(err.is_kind(ErrorKind::InvalidInput));
+///
+/// let errors = ValidationErrors::from_error(&err).unwrap();
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors.for_field("age").next().unwrap().code(), "min");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ValidationErrors {
+    /// Violations in the order they were found
+    violations: Vec<Violation>,
+    /// Error code used when converting into an [`Error`]
+    #[serde(skip, default = "default_code")]
+    code: i64,
+}
+
+/// Default error code for validation failures
+const fn default_code() -> i64 {
+    422
+}
+
+impl ValidationErrors {
+    /// Context key under which violations are stored when converted into an [`Error`]
+    pub const CONTEXT_KEY: &'static str = "violations";
+
+    /// Create an empty collection that converts into a `422` error
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            violations: Vec::new(),
+            code: default_code(),
+        }
+    }
+
+    /// Set the error code used when converting into an [`Error`] (builder pattern)
+    ///
+    /// Typically `400` or `422` (the default).
+    #[must_use]
+    pub const fn with_code(mut self, code: i64) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// Add a violation (builder pattern)
+    #[must_use]
+    pub fn with_violation(mut self, violation: Violation) -> Self {
+        self.push(violation);
+        self
+    }
+
+    /// Add a violation
+    pub fn push(&mut self, violation: Violation) {
+        self.violations.push(violation);
+    }
+
+    /// Add a violation without parameters
+    pub fn add(
+        &mut self,
+        field: impl Into<String>,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.push(Violation::new(field, code, message));
+    }
+
+    /// Get the error code used when converting into an [`Error`]
+    #[must_use]
+    pub const fn code(&self) -> i64 {
+        self.code
+    }
+
+    /// Get the number of violations
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.violations.len()
+    }
+
+    /// Check whether there are no violations
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Get all violations in the order they were found
+    #[must_use]
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Iterate over all violations in the order they were found
+    pub fn iter(&self) -> std::slice::Iter<'_, Violation> {
+        self.violations.iter()
+    }
+
+    /// Iterate over the violations of one field
+    pub fn for_field<'a>(&'a self, field: &'a str) -> impl Iterator<Item = &'a Violation> {
+        self.violations.iter().filter(move |v| v.field == field)
+    }
+
+    /// Return `Ok(())` if there are no violations, or the violations as an [`Error`]
+    ///
+    /// # Errors
+    ///
+    /// Returns the converted [`Error`] if at least one violation was recorded.
+    #[track_caller]
+    pub fn into_result(self) -> Result<(), Error> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self.into())
+        }
+    }
+
+    /// Recover the violations from an [`Error`] created from [`ValidationErrors`]
+    ///
+    /// Works on deserialized errors too, since violations live in the error context.
+    /// Returns `None` if the error carries no violations.
+    #[must_use]
+    pub fn from_error(error: &Error) -> Option<Self> {
+        let list = error.get_context_value(Self::CONTEXT_KEY)?.as_list()?;
+        let violations = list
+            .iter()
+            .map(Violation::from_context_value)
+            .collect::<Option<Vec<_>>>()?;
+        let code = error.code().unwrap_or_else(default_code);
+        Some(Self { violations, code })
+    }
+}
+
+impl Default for ValidationErrors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Validation failed")?;
+        for (i, violation) in self.violations.iter().enumerate() {
+            f.write_str(if i == 0 { ": " } else { "; " })?;
+            write!(f, "{violation}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for ValidationErrors {}
+
+impl Extend<Violation> for ValidationErrors {
+    fn extend<I: IntoIterator<Item = Violation>>(&mut self, iter: I) {
+        self.violations.extend(iter);
+    }
+}
+
+impl FromIterator<Violation> for ValidationErrors {
+    fn from_iter<I: IntoIterator<Item = Violation>>(iter: I) -> Self {
+        let mut errors = Self::new();
+        errors.extend(iter);
+        errors
+    }
+}
+
+impl<'a> IntoIterator for &'a ValidationErrors {
+    type Item = &'a Violation;
+    type IntoIter = std::slice::Iter<'a, Violation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.violations.iter()
+    }
+}
+
+impl IntoIterator for ValidationErrors {
+    type Item = Violation;
+    type IntoIter = std::vec::IntoIter<Violation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.violations.into_iter()
+    }
+}
+
+impl From<ValidationErrors> for Error {
+    #[track_caller]
+    fn from(errors: ValidationErrors) -> Self {
+        let violations: Vec<ContextValue> = errors.violations.into_iter().map(Into::into).collect();
+        Self::new("Validation failed")
+            .with_code_traced(errors.code)
+            .with_kind(ErrorKind::InvalidInput)
+            .with_context_value(ValidationErrors::CONTEXT_KEY, violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        errors.add("email", "invalid_email", "must be a valid email address");
+        errors.push(
+            Violation::new("age", "min", "must be at least 18")
+                .with_param("min", 18)
+                .with_param("actual", 16),
+        );
+        errors
+    }
+
+    #[test]
+    fn test_collect() {
+        let errors = sample();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors.code(), 422);
+        assert_eq!(errors.violations()[0].field(), "email");
+        assert_eq!(errors.for_field("age").count(), 1);
+        assert_eq!(errors.for_field("name").count(), 0);
+        assert_eq!(
+            errors.to_string(),
+            "Validation failed: email: must be a valid email address; age: must be at least 18"
+        );
+
+        let age = errors.for_field("age").next().unwrap();
+        assert_eq!(age.param("min"), Some(&ContextValue::Int(18)));
+    }
+
+    #[test]
+    fn test_into_result() {
+        assert!(ValidationErrors::new().into_result().is_ok());
+
+        let err = sample().with_code(400).into_result().unwrap_err();
+        assert_eq!(err.code(), Some(400));
+        assert_eq!(err.http_status(), 400);
+        assert_eq!(err.kind(), Some(ErrorKind::InvalidInput));
+        assert_eq!(err.message(), "Validation failed");
+        assert!(err.location().unwrap().file().ends_with("validation.rs"));
+    }
+
+    #[test]
+    fn test_serialize_list() {
+        assert_eq!(
+            serde_json::to_value(sample()).unwrap(),
+            json!([
+                {
+                    "field": "email",
+                    "code": "invalid_email",
+                    "message": "must be a valid email address",
+                },
+                {
+                    "field": "age",
+                    "code": "min",
+                    "message": "must be at least 18",
+                    "params": { "actual": 16, "min": 18 },
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_error_context() {
+        let err: Error = sample().into();
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            value["context"]["violations"],
+            serde_json::to_value(sample()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_from_error_round_trip() {
+        let err: Error = sample().with_code(400).into();
+        assert_eq!(
+            ValidationErrors::from_error(&err),
+            Some(sample().with_code(400))
+        );
+
+        // Violations survive the wire format
+        let json = serde_json::to_string(&err).unwrap();
+        let restored: Error = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            ValidationErrors::from_error(&restored),
+            Some(sample().with_code(400))
+        );
+
+        assert_eq!(ValidationErrors::from_error(&Error::new("other")), None);
+    }
+
+    #[test]
+    fn test_iterators() {
+        let errors: ValidationErrors = sample().into_iter().rev().collect();
+        let fields: Vec<&str> = errors.iter().map(Violation::field).collect();
+        assert_eq!(fields, ["age", "email"]);
+    }
+}
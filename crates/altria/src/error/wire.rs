@@ -0,0 +1,335 @@
+//! Serde wire format for [`Error`]
+//!
+//! An error is serialized as:
+//!
+//! ```json
+//! {
+//!   "code": 500,
+//!   "kind": "internal",
+//!   "status": 502,
+//!   "message": "Failed to load config",
+//!   "message_key": "config.load_failed",
+//!   "context": { "path": "/etc/app.toml", "attempt": 3 },
+//!   "sources": ["I/O error", "permission denied"]
+//! }
+//! ```
+//!
+//! Only `message` is required. The other fields are omitted when unset or
+//! empty, and survive a round trip:
+//! - `code` and `kind`
+//! - `status`: the HTTP status set with [`Error::with_http_status`]
+//! - `message_key`: the catalog key set with [`Error::with_message_key`]
+//! - `context`: entries in insertion order, with their typed values. Sensitive
+//!   values are serialized as `[REDACTED]` and come back as that string.
+//! - `sources`: the `Display` rendering of every error after the first in
+//!   [`Error::iter_error_chain`]. They come back as a linear chain of
+//!   [`OpaqueError`]s, so the rendered chain survives but the original error
+//!   types and the children of aggregate errors don't.
+//!
+//! The location, backtrace and span trace are never serialized, and neither
+//! are the explicit retry classification
+//! ([`with_retryability`](Error::with_retryability)) and the retry delay
+//! ([`with_retry_after`](Error::with_retry_after)). A deserialized error has
+//! none of them, so its [`retryability`](Error::retryability) is derived from
+//! its kind and code again.
+
+use super::{Context, Error, ErrorKind};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error as StdError;
+use std::fmt;
+
+/// A string-backed error reconstructed from a serialized source chain
+///
+/// # Examples
+///
+/// ```
+/// use altria::error::{Error, OpaqueError};
+/// use std::error::Error as StdError;
+///
+/// let json = r#"{"message":"Failed to read config","sources":["file not found"]}"#;
+/// let err: Error = serde_json::from_str(json).unwrap();
+///
+/// let source = err.source().unwrap();
+/// assert_eq!(source.to_string(), "file not found");
+/// assert!(source.downcast_ref::<OpaqueError>().is_some());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpaqueError {
+    /// Rendered message of the original error
+    message: String,
+    /// Next error in the chain
+    source: Option<Box<Self>>,
+}
+
+impl OpaqueError {
+    /// Create an opaque error from a rendered message
+    #[must_use]
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Get the rendered message
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Build a chain from rendered messages, outermost first
+    fn chain(messages: Vec<String>) -> Option<Self> {
+        messages.into_iter().rev().fold(None, |source, message| {
+            Some(Self {
+                message,
+                source: source.map(Box::new),
+            })
+        })
+    }
+}
+
+impl fmt::Display for OpaqueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl StdError for OpaqueError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_deref()
+            .map(|e| e as &(dyn StdError + 'static))
+    }
+}
+
+/// Borrowed wire representation used for serialization
+#[derive(Serialize)]
+struct ErrorRef<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<ErrorKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_key: Option<&'a str>,
+    #[serde(skip_serializing_if = "Context::is_empty")]
+    context: &'a Context,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sources: Vec<String>,
+}
+
+/// Owned wire representation used for deserialization
+#[derive(Deserialize)]
+struct ErrorRepr {
+    #[serde(default)]
+    code: Option<i64>,
+    #[serde(default)]
+    kind: Option<ErrorKind>,
+    #[serde(default)]
+    status: Option<u16>,
+    message: String,
+    #[serde(default)]
+    message_key: Option<String>,
+    #[serde(default)]
+    context: Context,
+    #[serde(default)]
+    sources: Vec<String>,
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        ErrorRef {
+            code: self.code,
+            kind: self.kind,
+            status: self.extra.as_ref().and_then(|extra| extra.http_status),
+            message: &self.message,
+            message_key: self.message_key(),
+            context: &self.context,
+            sources: self
+                .iter_error_chain()
+                .skip(1)
+                .map(ToString::to_string)
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Error {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = ErrorRepr::deserialize(deserializer)?;
+
+        let mut err = Self::bare(repr.message).with_context_map(repr.context);
+        err.code = repr.code;
+        err.kind = repr.kind;
+        if let Some(key) = repr.message_key {
+            err = err.with_message_key(key);
+        }
+        if let Some(status) = repr.status {
+            err = err.with_http_status(status);
+        }
+        if let Some(source) = OpaqueError::chain(repr.sources) {
+            err = err.with_source(source);
+        }
+        Ok(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_serialize_minimal() {
+        let err = Error::new("boom");
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({ "message": "boom" })
+        );
+    }
+
+    #[test]
+    fn test_serialize_full() {
+        let io_err = std::io::Error::other("disk full");
+        let inner = Error::new("write failed").with_source(io_err);
+        let err = Error::new("save failed")
+            .with_code(500)
+            .with_context_value("file", "a.txt")
+            .with_source(inner)
+            .with_backtrace();
+
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({
+                "code": 500,
+                "message": "save failed",
+                "context": { "file": "a.txt" },
+                "sources": ["write failed", "disk full"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let inner = Error::new("write failed")
+            .with_code(503)
+            .with_source(std::io::Error::other("disk full"));
+        let err = Error::new("save failed")
+            .with_code(500)
+            .with_kind(ErrorKind::Unavailable)
+            .with_message_key("file.save_failed")
+            .with_http_status(502)
+            .with_context_value("file", "a.txt")
+            .with_source(inner);
+
+        let json = serde_json::to_string(&err).unwrap();
+        let restored: Error = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.code(), Some(500));
+        assert_eq!(restored.kind(), Some(ErrorKind::Unavailable));
+        assert_eq!(restored.message(), "save failed");
+        assert_eq!(restored.message_key(), Some("file.save_failed"));
+        assert_eq!(restored.http_status(), 502);
+        assert_eq!(restored.get_context("file"), Some("a.txt"));
+        assert!(restored.backtrace().is_none());
+        assert!(restored.location().is_none());
+
+        let chain: Vec<String> = restored
+            .iter_error_chain()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            chain,
+            [
+                "[500] save failed (file: a.txt)",
+                "[503] write failed",
+                "disk full"
+            ]
+        );
+        assert!(
+            restored
+                .iter_error_chain()
+                .skip(1)
+                .all(|e| e.downcast_ref::<OpaqueError>().is_some())
+        );
+
+        // Serializing again yields the same document
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+    }
+
+    #[test]
+    fn test_sensitive_context_not_serialized() {
+        let err = Error::new("Login failed")
+            .with_context_value("user", "alice")
+            .with_sensitive_context("password", "hunter2");
+
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(!json.contains("hunter2"));
+
+        let restored: Error = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get_context("password"), Some("[REDACTED]"));
+    }
+
+    #[test]
+    fn test_retry_classification_not_serialized() {
+        let err = Error::new("Too many requests")
+            .with_code(400)
+            .with_retry_after(std::time::Duration::from_secs(30));
+        assert!(err.is_transient());
+
+        let restored: Error = serde_json::from_str(&serde_json::to_string(&err).unwrap()).unwrap();
+        assert_eq!(restored.retry_after(), None);
+        assert!(restored.is_permanent()); // Derived from the code again
+    }
+
+    #[test]
+    fn test_round_trip_typed_context() {
+        let err = Error::new("batch failed")
+            .with_context_value("ids", vec![1, 2, 3])
+            .with_context_value("ratio", 0.5)
+            .with_context_value("dry_run", false);
+
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            value["context"],
+            json!({ "ids": [1, 2, 3], "ratio": 0.5, "dry_run": false })
+        );
+
+        let json = serde_json::to_string(&err).unwrap();
+        let restored: Error = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.context(), err.context());
+        assert_eq!(restored.to_string(), err.to_string());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_deserialize_captures_no_span_trace() {
+        use tracing_error::ErrorLayer;
+        use tracing_subscriber::prelude::*;
+
+        let subscriber = tracing_subscriber::registry().with(ErrorLayer::default());
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("decode_response");
+            let _guard = span.enter();
+
+            let restored: Error = serde_json::from_str(r#"{"message":"remote failure"}"#).unwrap();
+            assert!(restored.span_trace().is_none());
+            assert!(Error::new("local failure").span_trace().is_some());
+        });
+    }
+
+    #[test]
+    fn test_deserialize_missing_message() {
+        let result: Result<Error, _> = serde_json::from_str(r#"{"code": 1}"#);
+        assert!(result.is_err());
+    }
+}
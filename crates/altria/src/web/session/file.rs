@@ -0,0 +1,482 @@
+//! Filesystem session storage
+
+use super::{Session, SessionStore};
+use crate::error::{Error, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Extension of session files
+const EXTENSION: &str = "json";
+
+/// Longest accepted session ID, keeping file names within common limits
+const MAX_ID_LEN: usize = 128;
+
+/// Modification time of sessions without expiration, 2100-01-01, so that
+/// `cleanup_expired` never reads them
+const NEVER_EXPIRES: Duration = Duration::from_secs(4_102_444_800);
+
+/// A [`SessionStore`] keeping one JSON file per session in a directory
+///
+/// Available with the `fs` feature, for single-host deployments where a
+/// database is not worth running. Each session is stored in `{dir}/{id}.json`.
+///
+/// - [`save`](SessionStore::save) writes and syncs a temporary file, then
+///   renames it over the previous one, so a half-written session is never
+///   read. The directory is not synced after the rename: a save may be lost if
+///   the system crashes right after it returns
+/// - Sessions past their [`expires_at`](Session::expires_at) are never returned
+///   by [`load`](SessionStore::load), and are removed when encountered
+/// - The modification time of each file is set to the session's expiration, or
+///   to the year 2100 for sessions without one, so
+///   [`cleanup_expired`](SessionStore::cleanup_expired) only reads files whose
+///   modification time has passed, then removes those whose stored expiration
+///   has passed too
+/// - An expired file is only removed if its size and modification time are
+///   unchanged since it was read, so a session saved again meanwhile is kept.
+///   Files have no compare-and-delete, so a save landing between that check
+///   and the removal is still lost
+///
+/// Session IDs are used as file names, so only IDs made of ASCII letters,
+/// digits, `-` and `_` (such as the default UUIDs) are accepted: loading any
+/// other ID finds nothing, and saving it fails. This blocks path traversal
+/// through IDs taken from requests. Saving a [discarded](Session::discard)
+/// session deletes it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use altria::web::session::{FileStore, SessionBuilder, SessionStore};
+/// use std::time::Duration;
+///
+/// # async fn example() -> altria::error::Result<()> {
+/// let store = FileStore::<u64>::new("/var/lib/myapp/sessions")?;
+///
+/// let session = SessionBuilder::new()
+///     .data(42)
+///     .expires_in(Duration::from_secs(3600))
+///     .build();
+/// store.save(&session).await?;
+///
+/// let loaded = store.load(session.id()).await?.unwrap();
+/// assert_eq!(loaded.data(), Some(42));
+/// # Ok(())
+/// # }
+/// ```
+pub struct FileStore<T> {
+    /// Directory holding the session files
+    dir: PathBuf,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> FileStore<T> {
+    /// Use a directory for session files, creating it if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .context("Failed to create session directory")
+            .with_context_value_lazy("path", || dir.display().to_string())?;
+        Ok(Self {
+            dir,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Get the directory holding the session files
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get the path of a session file, or `None` if the ID is not a safe file name
+    #[must_use]
+    pub fn path(&self, session_id: &str) -> Option<PathBuf> {
+        is_valid_id(session_id).then(|| self.dir.join(format!("{session_id}.{EXTENSION}")))
+    }
+
+    /// Remove a session file, ignoring files that are already gone
+    fn remove(path: &Path) -> Result<()> {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err)
+                .context("Failed to delete session")
+                .with_context_value_lazy("path", || path.display().to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove a session file if it still has the size and modification time it
+    /// had when read, returning whether it was removed
+    ///
+    /// Every save replaces the file and sets its modification time, so a changed
+    /// file means the session was saved again since.
+    fn remove_if_unchanged(path: &Path, read: &Metadata) -> Result<bool> {
+        let current = match fs::metadata(path) {
+            Ok(current) => current,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => {
+                return Err(err)
+                    .context("Failed to delete session")
+                    .with_context_value_lazy("path", || path.display().to_string());
+            }
+        };
+        if current.len() != read.len() || current.modified().ok() != read.modified().ok() {
+            return Ok(false);
+        }
+        Self::remove(path)?;
+        Ok(true)
+    }
+}
+
+impl<T> Clone for FileStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            dir: self.dir.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for FileStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileStore")
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> SessionStore<T> for FileStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    type Error = Error;
+
+    async fn save(&self, session: &Session<T>) -> Result<()> {
+        let path = self.path(session.id()).ok_or_else(|| {
+            Error::new("Invalid session ID for a file name")
+                .with_context_value("session_id", session.id())
+        })?;
+        if session.is_discarded() {
+            Self::remove(&path)?;
+            session.clear_modified();
+            return Ok(());
+        }
+
+        let json = serde_json::to_vec(session).context("Failed to serialize session")?;
+        // Dot-prefixed so that concurrent scans never mistake it for a session
+        let temp = self
+            .dir
+            .join(format!(".{}.{}.tmp", session.id(), Uuid::new_v4().simple()));
+        let written =
+            write_file(&temp, &json, session.expires_at()).and_then(|()| fs::rename(&temp, &path));
+        if let Err(err) = written {
+            let _ = fs::remove_file(&temp);
+            return Err(err)
+                .context("Failed to save session")
+                .with_context_value_lazy("path", || path.display().to_string());
+        }
+        session.clear_modified();
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<Session<T>>> {
+        let Some(path) = self.path(session_id) else {
+            return Ok(None);
+        };
+        let Some((session, metadata)) = read_session::<T>(&path)? else {
+            return Ok(None);
+        };
+        if session.is_expired() {
+            Self::remove_if_unchanged(&path, &metadata)?;
+            return Ok(None);
+        }
+        Ok(Some(session))
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<()> {
+        match self.path(session_id) {
+            Some(path) => Self::remove(&path),
+            None => Ok(()),
+        }
+    }
+
+    async fn cleanup_expired(&self) -> Result<usize> {
+        let entries = fs::read_dir(&self.dir)
+            .context("Failed to read session directory")
+            .with_context_value_lazy("path", || self.dir.display().to_string())?;
+        let now = SystemTime::now();
+        let mut deleted = 0;
+        for entry in entries {
+            let entry = entry.context("Failed to read session directory")?;
+            let path = entry.path();
+            let is_session_file = path.extension().is_some_and(|ext| ext == EXTENSION)
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(is_valid_id);
+            if !is_session_file {
+                continue;
+            }
+            // Files still in the future expire later, skip reading them
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() || metadata.modified().is_ok_and(|modified| modified > now) {
+                continue;
+            }
+            // Unreadable files are left for `load` to report
+            let Ok(Some((session, metadata))) = read_session::<T>(&path) else {
+                continue;
+            };
+            if session.is_expired() && Self::remove_if_unchanged(&path, &metadata)? {
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+/// Check that a session ID is safe to use as a file name
+fn is_valid_id(session_id: &str) -> bool {
+    !session_id.is_empty()
+        && session_id.len() <= MAX_ID_LEN
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Write and sync a new file, with its modification time set to `expires_at`,
+/// or [`NEVER_EXPIRES`] without expiration
+///
+/// Setting the modification time is best effort: if the filesystem refuses it,
+/// `cleanup_expired` reads the file on every scan instead.
+fn write_file(path: &Path, contents: &[u8], expires_at: Option<SystemTime>) -> io::Result<()> {
+    let mut file = File::create_new(path)?;
+    file.write_all(contents)?;
+    let _ = file.set_modified(expires_at.unwrap_or(UNIX_EPOCH + NEVER_EXPIRES));
+    file.sync_all()
+}
+
+/// Read a session file with the metadata of the file read, or `None` if it
+/// does not exist
+fn read_session<T>(path: &Path) -> Result<Option<(Session<T>, Metadata)>>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    let read = File::open(path).and_then(|mut file| {
+        let metadata = file.metadata()?;
+        let mut json = Vec::new();
+        file.read_to_end(&mut json)?;
+        Ok((json, metadata))
+    });
+    let (json, metadata) = match read {
+        Ok(read) => read,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err)
+                .context("Failed to load session")
+                .with_context_value_lazy("path", || path.display().to_string());
+        }
+    };
+    let session = serde_json::from_slice(&json)
+        .context("Failed to deserialize session")
+        .with_context_value_lazy("path", || path.display().to_string())?;
+    Ok(Some((session, metadata)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::session::SessionBuilder;
+    use crate::web::session::tests::{check_store, session};
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    /// A store in a fresh temporary directory
+    fn store() -> FileStore<u64> {
+        let dir = std::env::temp_dir().join(format!("altria-file-{}", Uuid::new_v4()));
+        FileStore::new(dir).unwrap()
+    }
+
+    fn file_names(store: &FileStore<u64>) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(store.dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_conformance() {
+        let store = store();
+        let ids = check_store(store.clone()).await;
+
+        // One file per session, with no temporary files left behind
+        let names: HashSet<String> = file_names(&store).into_iter().collect();
+        let expected: HashSet<String> = ids.iter().map(|id| format!("{id}.json")).collect();
+        assert_eq!(names, expected);
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_path_traversal_is_blocked() {
+        let store = store();
+        let outside = store.dir().join("..").join("outside.json");
+        fs::write(&outside, serde_json::to_vec(&session(1)).unwrap()).unwrap();
+
+        for id in ["../outside", "a/b", "a\\b", "", ".hidden", "id.json", "é"] {
+            assert!(store.path(id).is_none(), "{id}");
+            assert!(store.load(id).await.unwrap().is_none());
+            store.delete(id).await.unwrap();
+        }
+        assert!(outside.exists());
+
+        let session = SessionBuilder::new()
+            .id_generator(Box::new(|| "../escaped".to_string()))
+            .data(1)
+            .build();
+        let err = store.save(&session).await.unwrap_err();
+        assert_eq!(err.get_context("session_id"), Some("../escaped"));
+        assert!(!store.dir().join("../escaped.json").exists());
+
+        fs::remove_file(outside).unwrap();
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_modification_time_follows_expiration() {
+        let store = store();
+        let session = SessionBuilder::new()
+            .data(1)
+            .expires_in(Duration::from_secs(3600))
+            .build();
+        store.save(&session).await.unwrap();
+        let path = store.path(session.id()).unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        assert!(modified > SystemTime::now() + Duration::from_secs(3590));
+
+        // Without expiration, the modification time is far enough ahead that
+        // cleanup never reads the file
+        session.set_expiration(None);
+        store.save(&session).await.unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(modified, UNIX_EPOCH + NEVER_EXPIRES);
+
+        // Loading an expired session removes its file
+        session.set_expiration(Some(SystemTime::now() - Duration::from_secs(1)));
+        store.save(&session).await.unwrap();
+        assert!(store.load(session.id()).await.unwrap().is_none());
+        assert!(!path.exists());
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_skips_other_files() {
+        let store = store();
+        let expired = session(1);
+        expired.set_expiration(Some(SystemTime::now() - Duration::from_secs(1)));
+        store.save(&expired).await.unwrap();
+        fs::write(store.dir().join("notes.txt"), "not a session").unwrap();
+
+        assert_eq!(store.cleanup_expired().await.unwrap(), 1);
+        assert_eq!(file_names(&store), ["notes.txt"]);
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_checks_stored_expiration() {
+        let store = store();
+        let session = SessionBuilder::new()
+            .data(1)
+            .expires_in(Duration::from_secs(3600))
+            .build();
+        store.save(&session).await.unwrap();
+
+        // A stale modification time alone does not delete a live session
+        let path = store.path(session.id()).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(store.cleanup_expired().await.unwrap(), 0);
+        assert!(path.exists());
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_file_saved_again_is_kept() {
+        let store = store();
+        let session = session(1);
+        session.set_expiration(Some(SystemTime::now() - Duration::from_secs(1)));
+        store.save(&session).await.unwrap();
+        let path = store.path(session.id()).unwrap();
+        let (_, metadata) = read_session::<u64>(&path).unwrap().unwrap();
+
+        // Saved again between reading the expired file and removing it
+        session.set_expiration(Some(SystemTime::now() + Duration::from_secs(3600)));
+        store.save(&session).await.unwrap();
+        assert!(!FileStore::<u64>::remove_if_unchanged(&path, &metadata).unwrap());
+        assert!(store.load(session.id()).await.unwrap().is_some());
+
+        let (_, metadata) = read_session::<u64>(&path).unwrap().unwrap();
+        assert!(FileStore::<u64>::remove_if_unchanged(&path, &metadata).unwrap());
+        assert!(!path.exists());
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_file() {
+        let store = store();
+        let session = session(1);
+        fs::write(store.path(session.id()).unwrap(), "{").unwrap();
+
+        let err = store.load(session.id()).await.unwrap_err();
+        assert!(err.get_context("path").is_some());
+        // Cleanup leaves files it cannot read
+        assert_eq!(store.cleanup_expired().await.unwrap(), 0);
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_saves() {
+        let store = store();
+        let session = session(0);
+        let tasks: Vec<_> = (0..4)
+            .map(|worker| {
+                let (store, session) = (store.clone(), session.clone());
+                tokio::spawn(async move {
+                    for i in 0..25 {
+                        session.update_data(Some(worker * 1_000 + i));
+                        store.save(&session).await.unwrap();
+                        // Every read sees a complete file
+                        assert!(store.load(session.id()).await.unwrap().is_some());
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(file_names(&store).len(), 1);
+        fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_is_valid_id() {
+        assert!(is_valid_id(&Uuid::new_v4().to_string()));
+        assert!(is_valid_id("user_42-a"));
+        assert!(!is_valid_id(&"a".repeat(MAX_ID_LEN + 1)));
+        assert!(!is_valid_id(".."));
+    }
+}
@@ -0,0 +1,369 @@
+//! In-memory session storage
+
+use super::{Session, SessionStore};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+/// An in-memory [`SessionStore`] for development and single-node deployments
+///
+/// - Sessions past their [`expires_at`](Session::expires_at) are never returned
+///   by [`load`](SessionStore::load), and are removed when encountered
+/// - An optional bound on the number of sessions evicts the least recently
+///   used session (by save or load) when exceeded
+/// - Clones share the same storage, so the store can be put in application state
+///
+/// The store keeps its own copy of each session: changes to a loaded session
+/// are only visible to other loads once it is saved again. Saving a
+/// [discarded](Session::discard) session deletes it.
+///
+/// # Examples
+///
+/// ```
+/// use altria::web::session::{MemoryStore, SessionBuilder, SessionStore};
+/// use std::num::NonZeroUsize;
+/// use std::time::Duration;
+///
+/// # async fn example() {
+/// let store = MemoryStore::with_capacity(NonZeroUsize::new(10_000).unwrap());
+///
+/// let session = SessionBuilder::<u64>::new()
+///     .data(42)
+///     .expires_in(Duration::from_secs(3600))
+///     .build();
+/// store.save(&session).await.unwrap();
+///
+/// let loaded = store.load(session.id()).await.unwrap().unwrap();
+/// assert_eq!(loaded.data(), Some(42));
+/// # }
+/// ```
+pub struct MemoryStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    /// Shared storage, protected by a single lock since loads update the LRU order
+    inner: Arc<Mutex<Inner<T>>>,
+}
+
+/// Stored sessions and their recency order
+struct Inner<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    /// Sessions by ID, with the tick of their last use
+    sessions: HashMap<String, (Session<T>, u64)>,
+    /// Session IDs by the tick of their last use, least recent first
+    recency: BTreeMap<u64, String>,
+    /// Monotonic counter ordering uses
+    tick: u64,
+    /// Maximum number of sessions, if bounded
+    max_sessions: Option<NonZeroUsize>,
+}
+
+impl<T> Inner<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    /// Insert or replace a session, marking it as most recently used
+    fn insert(&mut self, session: Session<T>) {
+        self.tick += 1;
+        let id = session.id().to_string();
+        if let Some((_, used)) = self.sessions.insert(id.clone(), (session, self.tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.tick, id);
+    }
+
+    /// Mark a stored session as most recently used
+    fn touch(&mut self, session_id: &str) {
+        self.tick += 1;
+        if let Some((_, used)) = self.sessions.get_mut(session_id) {
+            self.recency.remove(used);
+            *used = self.tick;
+            self.recency.insert(self.tick, session_id.to_string());
+        }
+    }
+
+    /// Remove a session, returning whether it was stored
+    fn remove(&mut self, session_id: &str) -> bool {
+        match self.sessions.remove(session_id) {
+            Some((_, used)) => {
+                self.recency.remove(&used);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Evict least recently used sessions until the bound, if any, is respected
+    fn evict(&mut self) {
+        let Some(max) = self.max_sessions else {
+            return;
+        };
+        while self.sessions.len() > max.get() {
+            let Some((_, id)) = self.recency.pop_first() else {
+                break;
+            };
+            self.sessions.remove(&id);
+        }
+    }
+}
+
+impl<T> MemoryStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    /// Create an unbounded store
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_bound(None)
+    }
+
+    /// Create a store holding at most `max_sessions` sessions
+    ///
+    /// When a save exceeds the bound, the least recently used session is
+    /// evicted.
+    #[must_use]
+    pub fn with_capacity(max_sessions: NonZeroUsize) -> Self {
+        Self::with_bound(Some(max_sessions))
+    }
+
+    fn with_bound(max_sessions: Option<NonZeroUsize>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                sessions: HashMap::new(),
+                recency: BTreeMap::new(),
+                tick: 0,
+                max_sessions,
+            })),
+        }
+    }
+
+    /// Get the maximum number of stored sessions, if bounded
+    #[must_use]
+    pub fn max_sessions(&self) -> Option<NonZeroUsize> {
+        self.inner.lock().max_sessions
+    }
+
+    /// Get the number of stored sessions, including expired ones not yet cleaned up
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.lock().sessions.len()
+    }
+
+    /// Check whether the store has no sessions
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().sessions.is_empty()
+    }
+}
+
+impl<T> Default for MemoryStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for MemoryStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> fmt::Debug for MemoryStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryStore")
+            .field("len", &self.len())
+            .field("max_sessions", &self.max_sessions())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> SessionStore<T> for MemoryStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    type Error = Infallible;
+
+    async fn save(&self, session: &Session<T>) -> Result<(), Self::Error> {
+        let mut inner = self.inner.lock();
+        if session.is_discarded() {
+            inner.remove(session.id());
+        } else {
+            inner.insert(session.detached());
+            inner.evict();
+        }
+        drop(inner);
+        session.clear_modified();
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<Session<T>>, Self::Error> {
+        let mut inner = self.inner.lock();
+        let Some((session, _)) = inner.sessions.get(session_id) else {
+            return Ok(None);
+        };
+        if session.is_expired() {
+            inner.remove(session_id);
+            return Ok(None);
+        }
+        let session = session.detached();
+        inner.touch(session_id);
+        drop(inner);
+        Ok(Some(session))
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<(), Self::Error> {
+        self.inner.lock().remove(session_id);
+        Ok(())
+    }
+
+    async fn cleanup_expired(&self) -> Result<usize, Self::Error> {
+        let mut inner = self.inner.lock();
+        let expired: Vec<String> = inner
+            .sessions
+            .iter()
+            .filter(|(_, (session, _))| session.is_expired())
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &expired {
+            inner.remove(id);
+        }
+        drop(inner);
+        Ok(expired.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::session::tests::{check_store, session};
+    use std::collections::HashSet;
+    use std::time::{Duration, SystemTime};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_conformance() {
+        let store = MemoryStore::new();
+        let ids = check_store(store.clone()).await;
+        let stored: HashSet<String> = store.inner.lock().sessions.keys().cloned().collect();
+        assert_eq!(stored, ids);
+    }
+
+    #[tokio::test]
+    async fn test_stored_copy_is_detached() {
+        let store = MemoryStore::new();
+        let session = session(1);
+        store.save(&session).await.unwrap();
+
+        // Unsaved changes are not visible to other loads
+        session.update_data(Some(2));
+        let loaded = store.load(session.id()).await.unwrap().unwrap();
+        assert_eq!(loaded.data(), Some(1));
+        assert!(!loaded.is_modified());
+
+        loaded.update_data(Some(3));
+        assert_eq!(
+            store.load(session.id()).await.unwrap().unwrap().data(),
+            Some(1)
+        );
+
+        store.save(&loaded).await.unwrap();
+        assert_eq!(
+            store.load(session.id()).await.unwrap().unwrap().data(),
+            Some(3)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_is_removed_on_load() {
+        let store = MemoryStore::new();
+        let expired = session(1);
+        expired.set_expiration(Some(SystemTime::now() - Duration::from_secs(1)));
+        store.save(&expired).await.unwrap();
+        assert_eq!(store.len(), 1);
+
+        assert!(store.load(expired.id()).await.unwrap().is_none());
+        assert!(store.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction() {
+        let store = MemoryStore::with_capacity(NonZeroUsize::new(2).unwrap());
+        let (a, b, c) = (session(1), session(2), session(3));
+        store.save(&a).await.unwrap();
+        store.save(&b).await.unwrap();
+
+        // Loading `a` makes `b` the least recently used
+        store.load(a.id()).await.unwrap();
+        store.save(&c).await.unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(store.load(b.id()).await.unwrap().is_none());
+        assert!(store.load(a.id()).await.unwrap().is_some());
+        assert!(store.load(c.id()).await.unwrap().is_some());
+
+        // Re-saving an existing session doesn't evict
+        store.save(&a).await.unwrap();
+        assert_eq!(store.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bound_is_shared_by_clones() {
+        let store = MemoryStore::with_capacity(NonZeroUsize::new(2).unwrap());
+        let clone = store.clone();
+        assert_eq!(clone.max_sessions(), NonZeroUsize::new(2));
+        assert_eq!(MemoryStore::<u64>::new().max_sessions(), None);
+
+        for data in 0..3 {
+            clone.save(&session(data)).await.unwrap();
+        }
+        assert_eq!(store.len(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_bounded_access() {
+        let store = MemoryStore::<u64>::with_capacity(NonZeroUsize::new(16).unwrap());
+        let tasks: Vec<_> = (0..8)
+            .map(|worker| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    for i in 0..200 {
+                        let session = session(worker * 1_000 + i);
+                        store.save(&session).await.unwrap();
+                        assert!(store.len() <= 16);
+                        store.load(session.id()).await.unwrap();
+                        store.cleanup_expired().await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(store.len(), 16);
+
+        // The recency index stays consistent with the sessions
+        let inner = store.inner.lock();
+        let consistent = inner.recency.len() == inner.sessions.len()
+            && inner
+                .recency
+                .iter()
+                .all(|(tick, id)| inner.sessions[id].1 == *tick);
+        drop(inner);
+        assert!(consistent);
+    }
+}
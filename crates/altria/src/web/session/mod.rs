@@ -6,7 +6,11 @@
 //! - Optional expiration tracking
 //! - Change tracking for efficient persistence
 //! - Full serialization support via serde
-//! - Extensible storage backend via the `SessionStore` trait
+//! - Extensible storage backend via the `SessionStore` trait, with an
+//!   in-memory implementation ([`MemoryStore`]), a `SQLite` one (`SqliteStore`,
+//!   `sqlite` feature), a Redis one (`RedisStore`, `redis` feature), a
+//!   `PostgreSQL` one (`PostgresStore`, `postgres` feature) and a filesystem
+//!   one (`FileStore`, `fs` feature)
 //! - Customizable session ID generation via builder pattern
 //!
 //! # Examples
@@ -36,6 +40,26 @@
 //! assert!(session.is_modified());
 //! ```
 
+#[cfg(feature = "fs")]
+mod file;
+mod memory;
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "fs")]
+pub use file::FileStore;
+pub use memory::MemoryStore;
+#[cfg(feature = "postgres")]
+pub use postgres::{DEFAULT_CLEANUP_BATCH_SIZE, PostgresStore};
+#[cfg(feature = "redis")]
+pub use redis::{DEFAULT_KEY_PREFIX, RedisStore};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -44,6 +68,10 @@ use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
+/// Default name of the sessions table of SQL-backed stores
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub const DEFAULT_TABLE_NAME: &str = "sessions";
+
 /// Default session data structure with essential user information
 ///
 /// This is a simple data structure with public fields for direct access.
@@ -505,6 +533,20 @@ where
     pub fn clear_modified(&self) {
         self.state.write().modified = false;
     }
+
+    /// Create an unmodified copy of the session that does not share state with it
+    ///
+    /// Stores keep detached copies, so later changes to a handle are only
+    /// persisted when it is saved again.
+    fn detached(&self) -> Self {
+        let mut state = self.state.read().clone();
+        state.modified = false;
+        Self {
+            id: self.id.clone(),
+            created_at: self.created_at,
+            state: Arc::new(RwLock::new(state)),
+        }
+    }
 }
 
 // Implement Debug manually to show relevant fields
@@ -852,6 +894,11 @@ where
 /// Implement this trait to provide custom session storage solutions
 /// (e.g., in-memory, Redis, database, etc.).
 ///
+/// The methods are `async`, but an implementation may still block the calling
+/// task: the `SQLite` and filesystem stores do their I/O synchronously. That is
+/// fine for the short operations of a session store, but worth knowing before
+/// sharing a runtime with latency-sensitive work.
+///
 /// # Type Parameters
 ///
 /// - `T`: The session data type
@@ -976,6 +1023,7 @@ const _: () = {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
     use std::thread;
 
     #[test]
@@ -1278,4 +1326,128 @@ mod tests {
         );
         assert_eq!(session.get_context("theme"), restored.get_context("theme"));
     }
+
+    /// A session holding `data`, without expiration
+    pub(super) fn session(data: u64) -> Session<u64> {
+        SessionBuilder::new().data(data).build()
+    }
+
+    /// Check the behavior every [`SessionStore`] shares
+    ///
+    /// Store tests call this on a fresh store, then check what is specific to
+    /// their backend. Needs a multi-threaded runtime. Returns the IDs of the
+    /// sessions left in the store.
+    pub(super) async fn check_store<S>(store: S) -> HashSet<String>
+    where
+        S: SessionStore<u64> + Clone + 'static,
+    {
+        check_save_load_delete(&store).await;
+        check_discarded_session_is_deleted(&store).await;
+        let mut ids = check_expiration(&store).await;
+        ids.extend(check_concurrent_access(store).await);
+        ids
+    }
+
+    async fn check_save_load_delete<S: SessionStore<u64>>(store: &S) {
+        let session = SessionBuilder::new()
+            .data(1)
+            .expires_in(Duration::from_secs(3600))
+            .context("theme", "dark")
+            .build();
+        store.save(&session).await.unwrap();
+        assert!(!session.is_modified());
+
+        let loaded = store.load(session.id()).await.unwrap().unwrap();
+        assert_eq!(loaded.data(), Some(1));
+        assert_eq!(loaded.created_at(), session.created_at());
+        assert_eq!(loaded.expires_at(), session.expires_at());
+        assert_eq!(loaded.get_context("theme").as_deref(), Some("dark"));
+        assert!(!loaded.is_modified());
+
+        // Saving again replaces the stored session
+        loaded.update_data(Some(2));
+        store.save(&loaded).await.unwrap();
+        assert_eq!(
+            store.load(session.id()).await.unwrap().unwrap().data(),
+            Some(2)
+        );
+
+        store.delete(session.id()).await.unwrap();
+        assert!(store.load(session.id()).await.unwrap().is_none());
+        store.delete(session.id()).await.unwrap();
+        assert!(store.load("missing").await.unwrap().is_none());
+    }
+
+    async fn check_discarded_session_is_deleted<S: SessionStore<u64>>(store: &S) {
+        let session = session(1);
+        store.save(&session).await.unwrap();
+
+        session.discard();
+        store.save(&session).await.unwrap();
+        assert!(store.load(session.id()).await.unwrap().is_none());
+        assert!(!session.is_modified());
+    }
+
+    /// Returns the IDs of the sessions left in the store
+    async fn check_expiration<S: SessionStore<u64>>(store: &S) -> HashSet<String> {
+        let expired = session(1);
+        expired.set_expiration(Some(SystemTime::now() - Duration::from_secs(1)));
+        let live = SessionBuilder::new()
+            .data(2)
+            .expires_in(Duration::from_secs(3600))
+            .build();
+        let permanent = session(3);
+        for session in [&expired, &live, &permanent] {
+            store.save(session).await.unwrap();
+        }
+        assert!(store.load(expired.id()).await.unwrap().is_none());
+
+        // How many sessions the first cleanup finds depends on the backend
+        store.cleanup_expired().await.unwrap();
+        assert_eq!(store.cleanup_expired().await.unwrap(), 0);
+        assert!(store.load(live.id()).await.unwrap().is_some());
+        assert!(store.load(permanent.id()).await.unwrap().is_some());
+        HashSet::from([live.id().to_string(), permanent.id().to_string()])
+    }
+
+    /// Returns the IDs of the sessions left in the store
+    async fn check_concurrent_access<S>(store: S) -> HashSet<String>
+    where
+        S: SessionStore<u64> + Clone + 'static,
+    {
+        // The futures of a generic store aren't known to be `Send`, so each
+        // worker drives its own on a blocking thread of the runtime
+        let runtime = tokio::runtime::Handle::current();
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let (store, runtime) = (store.clone(), runtime.clone());
+                tokio::task::spawn_blocking(move || {
+                    runtime.block_on(async {
+                        let mut sessions = Vec::new();
+                        for i in 0..25 {
+                            let session = session(worker * 1_000 + i);
+                            store.save(&session).await.unwrap();
+                            let loaded = store.load(session.id()).await.unwrap().unwrap();
+                            assert_eq!(loaded.data(), session.data());
+                            sessions.push(session);
+                        }
+                        sessions
+                    })
+                })
+            })
+            .collect();
+
+        let mut sessions = Vec::new();
+        for worker in workers {
+            sessions.extend(worker.await.unwrap());
+        }
+        for session in &sessions {
+            let loaded = store.load(session.id()).await.unwrap().unwrap();
+            assert_eq!(loaded.data(), session.data());
+        }
+        sessions
+            .iter()
+            .map(|session| session.id().to_string())
+            .collect()
+    }
 }
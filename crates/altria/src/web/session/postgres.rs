@@ -0,0 +1,582 @@
+//! `PostgreSQL` session storage
+
+use super::{DEFAULT_TABLE_NAME, Session, SessionStore};
+use crate::error::{Error, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+use sqlx::{AssertSqlSafe, PgPool};
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default number of rows deleted per statement by `cleanup_expired`
+pub const DEFAULT_CLEANUP_BATCH_SIZE: usize = 1000;
+
+/// Table recording the schema version of each sessions table
+const MIGRATIONS_TABLE: &str = "altria_session_migrations";
+
+/// Longest accepted table name
+///
+/// `PostgreSQL` truncates identifiers to 63 bytes, which must leave room for
+/// the `_expires_at_idx` suffix of the expiration index.
+const MAX_TABLE_NAME_LEN: usize = 48;
+
+/// Schema migrations of a sessions table, applied in order
+///
+/// `$table` is replaced with the quoted table name, and `$index` with the
+/// quoted name of the expiration index.
+const MIGRATIONS: &[&str] = &[
+    // 1: Sessions as JSONB with an indexed expiration
+    "CREATE TABLE $table (
+        id TEXT PRIMARY KEY NOT NULL,
+        session JSONB NOT NULL,
+        expires_at TIMESTAMPTZ
+    );
+    CREATE INDEX $index ON $table (expires_at);",
+];
+
+/// A [`SessionStore`] persisting sessions in a `PostgreSQL` database
+///
+/// Available with the `postgres` feature. Each session is stored as `JSONB` in
+/// a single row, next to an indexed `expires_at` column (`NULL` for sessions
+/// that never expire). [`save`](SessionStore::save) is an upsert, expired
+/// sessions are never returned by [`load`](SessionStore::load), and
+/// [`cleanup_expired`](SessionStore::cleanup_expired) deletes them in batches
+/// so that a large backlog does not hold locks on the whole table.
+///
+/// The table is created, or migrated to the current schema, when the store is
+/// created. Migrations take an advisory lock, so several instances of a
+/// service may start at once. Saving a [discarded](Session::discard) session
+/// deletes it.
+///
+/// `PostgreSQL` cannot store the NUL character in `JSONB` strings, so saving a
+/// session whose data or context contains one fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use altria::web::session::{PostgresStore, SessionBuilder, SessionStore};
+/// use std::time::Duration;
+///
+/// # async fn example() -> altria::error::Result<()> {
+/// let store = PostgresStore::<u64>::connect("postgres://localhost/app").await?;
+///
+/// let session = SessionBuilder::new()
+///     .data(42)
+///     .expires_in(Duration::from_secs(3600))
+///     .build();
+/// store.save(&session).await?;
+///
+/// let loaded = store.load(session.id()).await?.unwrap();
+/// assert_eq!(loaded.data(), Some(42));
+/// # Ok(())
+/// # }
+/// ```
+pub struct PostgresStore<T> {
+    /// Connection pool, shared between clones
+    pool: PgPool,
+    /// Name of the sessions table
+    table: String,
+    /// Number of rows deleted per statement by `cleanup_expired`
+    cleanup_batch_size: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> PostgresStore<T> {
+    /// Connect to a database and use the default `sessions` table
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be reached or migrated.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let pool = PgPool::connect(url)
+            .await
+            .context("Failed to connect to session database")?;
+        Self::new(pool).await
+    }
+
+    /// Use an existing pool and the default `sessions` table
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the table cannot be created or migrated.
+    pub async fn new(pool: PgPool) -> Result<Self> {
+        Self::with_table_name(pool, DEFAULT_TABLE_NAME).await
+    }
+
+    /// Use an existing pool and a custom table name
+    ///
+    /// The name must start with an ASCII letter or `_`, contain only ASCII
+    /// letters, digits and `_`, and be at most 48 characters long. It may not
+    /// be schema-qualified: the table lives in the first schema of the
+    /// connection's `search_path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid, the table cannot be created or
+    /// migrated, or it was migrated by a newer version of this crate.
+    pub async fn with_table_name(pool: PgPool, table: &str) -> Result<Self> {
+        if !is_valid_table_name(table) {
+            return Err(Error::new("Invalid session table name").with_context_value("table", table));
+        }
+        migrate(&pool, table).await?;
+        Ok(Self {
+            pool,
+            table: table.to_string(),
+            cleanup_batch_size: DEFAULT_CLEANUP_BATCH_SIZE,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Set the number of rows deleted per statement by `cleanup_expired`
+    /// (builder pattern)
+    ///
+    /// Defaults to [`DEFAULT_CLEANUP_BATCH_SIZE`]. A size of zero is treated
+    /// as one.
+    #[must_use]
+    pub fn with_cleanup_batch_size(mut self, size: usize) -> Self {
+        self.cleanup_batch_size = size.max(1);
+        self
+    }
+
+    /// Get the name of the sessions table
+    #[must_use]
+    pub fn table_name(&self) -> &str {
+        &self.table
+    }
+
+    /// Get the number of rows deleted per statement by `cleanup_expired`
+    #[must_use]
+    pub fn cleanup_batch_size(&self) -> usize {
+        self.cleanup_batch_size
+    }
+
+    /// Get the connection pool
+    #[must_use]
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+}
+
+impl<T> Clone for PostgresStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            table: self.table.clone(),
+            cleanup_batch_size: self.cleanup_batch_size,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for PostgresStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PostgresStore")
+            .field("table", &self.table)
+            .field("cleanup_batch_size", &self.cleanup_batch_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> SessionStore<T> for PostgresStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    type Error = Error;
+
+    async fn save(&self, session: &Session<T>) -> Result<()> {
+        if session.is_discarded() {
+            self.delete(session.id()).await?;
+            session.clear_modified();
+            return Ok(());
+        }
+
+        let json = serde_json::to_string(session).context("Failed to serialize session")?;
+        if has_nul_escape(&json) {
+            return Err(
+                Error::new("Session contains a NUL character, which JSONB cannot store")
+                    .with_context_value("session_id", session.id()),
+            );
+        }
+        let sql = format!(
+            "INSERT INTO \"{}\" (id, session, expires_at) VALUES ($1, $2::jsonb, to_timestamp($3))
+             ON CONFLICT (id) DO UPDATE SET session = excluded.session, expires_at = excluded.expires_at",
+            self.table
+        );
+        sqlx::query(AssertSqlSafe(sql))
+            .bind(session.id())
+            .bind(json)
+            .bind(session.expires_at().map(unix_seconds))
+            .execute(&self.pool)
+            .await
+            .context("Failed to save session")
+            .with_context_value("table", self.table.as_str())?;
+        session.clear_modified();
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<Session<T>>> {
+        let sql = format!(
+            "SELECT session::text FROM \"{}\" WHERE id = $1 AND (expires_at IS NULL OR expires_at > now())",
+            self.table
+        );
+        let json: Option<String> = sqlx::query_scalar(AssertSqlSafe(sql))
+            .bind(session_id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to load session")
+            .with_context_value("table", self.table.as_str())?;
+        json.map(|json| serde_json::from_str(&json).context("Failed to deserialize session"))
+            .transpose()
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<()> {
+        let sql = format!("DELETE FROM \"{}\" WHERE id = $1", self.table);
+        sqlx::query(AssertSqlSafe(sql))
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete session")
+            .with_context_value("table", self.table.as_str())?;
+        Ok(())
+    }
+
+    async fn cleanup_expired(&self) -> Result<usize> {
+        // Rows locked by a concurrent cleanup are skipped rather than waited on
+        let sql = format!(
+            "DELETE FROM \"{0}\" WHERE id IN (
+                SELECT id FROM \"{0}\" WHERE expires_at <= now() LIMIT $1 FOR UPDATE SKIP LOCKED
+            )",
+            self.table
+        );
+        let batch_size = u64::try_from(self.cleanup_batch_size).unwrap_or(u64::MAX);
+        let mut deleted = 0;
+        loop {
+            let batch = sqlx::query(AssertSqlSafe(sql.as_str()))
+                .bind(i64::try_from(batch_size).unwrap_or(i64::MAX))
+                .execute(&self.pool)
+                .await
+                .context("Failed to clean up expired sessions")
+                .with_context_value("table", self.table.as_str())?
+                .rows_affected();
+            deleted += usize::try_from(batch).unwrap_or(usize::MAX);
+            if batch < batch_size {
+                return Ok(deleted);
+            }
+        }
+    }
+}
+
+/// Check that a table name is a plain SQL identifier short enough for its index
+fn is_valid_table_name(table: &str) -> bool {
+    let mut chars = table.chars();
+    table.len() <= MAX_TABLE_NAME_LEN
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Create the sessions table or bring it up to the current schema
+async fn migrate(pool: &PgPool, table: &str) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .context("Failed to migrate session table")?;
+
+    // Serialize concurrent migrations until the transaction ends
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(MIGRATIONS_TABLE)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to lock session migrations")?;
+
+    sqlx::raw_sql(AssertSqlSafe(format!(
+        "CREATE TABLE IF NOT EXISTS {MIGRATIONS_TABLE} (
+            table_name TEXT PRIMARY KEY NOT NULL,
+            version INTEGER NOT NULL
+        );"
+    )))
+    .execute(&mut *tx)
+    .await
+    .context("Failed to create session migrations table")?;
+
+    let version: Option<i32> = sqlx::query_scalar(AssertSqlSafe(format!(
+        "SELECT version FROM {MIGRATIONS_TABLE} WHERE table_name = $1"
+    )))
+    .bind(table)
+    .fetch_optional(&mut *tx)
+    .await
+    .context("Failed to read session table version")?;
+    let version = version.map_or(0, |version| usize::try_from(version).unwrap_or(0));
+    if version > MIGRATIONS.len() {
+        // Recording our version would downgrade the table under newer code
+        return Err(Error::new("Session table was migrated by a newer version")
+            .with_context_value("table", table)
+            .with_context_value("version", version));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let sql = migration
+            .replace("$table", &format!("\"{table}\""))
+            .replace("$index", &format!("\"{table}_expires_at_idx\""));
+        sqlx::raw_sql(AssertSqlSafe(sql))
+            .execute(&mut *tx)
+            .await
+            .context("Failed to migrate session table")
+            .with_context_value("table", table)
+            .with_context_value("version", index + 1)?;
+    }
+
+    sqlx::query(AssertSqlSafe(format!(
+        "INSERT INTO {MIGRATIONS_TABLE} (table_name, version) VALUES ($1, $2)
+         ON CONFLICT (table_name) DO UPDATE SET version = excluded.version"
+    )))
+    .bind(table)
+    .bind(i32::try_from(MIGRATIONS.len()).unwrap_or(i32::MAX))
+    .execute(&mut *tx)
+    .await
+    .context("Failed to record session table version")?;
+    tx.commit().await.context("Failed to migrate session table")
+}
+
+/// Check whether serialized JSON contains an escaped NUL character (`\u0000`)
+fn has_nul_escape(json: &str) -> bool {
+    let mut rest = json;
+    while let Some(index) = rest.find('\\') {
+        let escape = &rest[index + 1..];
+        if escape.starts_with("u0000") {
+            return true;
+        }
+        // Skip the escaped character, which may be a backslash itself
+        rest = escape.get(1..).unwrap_or_default();
+    }
+    false
+}
+
+/// Convert a time to fractional Unix seconds, as taken by `to_timestamp`
+fn unix_seconds(time: SystemTime) -> f64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs_f64(),
+        Err(before) => -before.duration().as_secs_f64(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ContextValue;
+    use crate::web::session::SessionBuilder;
+    use crate::web::session::tests::{check_store, session};
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    /// Environment variable holding the URL of a database to test against
+    ///
+    /// Tests needing a database are ignored by default, run them with
+    /// `cargo test --features postgres -- --ignored`.
+    const DATABASE_URL_VAR: &str = "ALTRIA_TEST_POSTGRES_URL";
+
+    /// Connect to the test database
+    async fn pool() -> PgPool {
+        let url = std::env::var(DATABASE_URL_VAR)
+            .unwrap_or_else(|_| panic!("{DATABASE_URL_VAR} must be set to run this test"));
+        PgPool::connect(&url).await.unwrap()
+    }
+
+    /// A store on a fresh table, so that tests can run concurrently
+    async fn store() -> PostgresStore<u64> {
+        let table = format!("sessions_{}", uuid::Uuid::new_v4().simple());
+        PostgresStore::with_table_name(pool().await, &table)
+            .await
+            .unwrap()
+    }
+
+    async fn drop_table(store: &PostgresStore<u64>) {
+        sqlx::raw_sql(AssertSqlSafe(format!(
+            "DROP TABLE IF EXISTS \"{0}\"; DELETE FROM {MIGRATIONS_TABLE} WHERE table_name = '{0}'",
+            store.table
+        )))
+        .execute(&store.pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[ignore = "requires ALTRIA_TEST_POSTGRES_URL"]
+    async fn test_conformance() {
+        let store = store().await;
+        let ids = check_store(store.clone()).await;
+
+        let stored: Vec<String> =
+            sqlx::query_scalar(AssertSqlSafe(format!("SELECT id FROM \"{}\"", store.table)))
+                .fetch_all(&store.pool)
+                .await
+                .unwrap();
+        assert_eq!(stored.into_iter().collect::<HashSet<_>>(), ids);
+        drop_table(&store).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires ALTRIA_TEST_POSTGRES_URL"]
+    async fn test_stored_as_jsonb() {
+        let store = store().await;
+        let session = session(7);
+        store.save(&session).await.unwrap();
+
+        let data: i64 = sqlx::query_scalar(AssertSqlSafe(format!(
+            "SELECT (session->'state'->>'data')::bigint FROM \"{}\" WHERE id = $1",
+            store.table
+        )))
+        .bind(session.id())
+        .fetch_one(&store.pool)
+        .await
+        .unwrap();
+        assert_eq!(data, 7);
+        drop_table(&store).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires ALTRIA_TEST_POSTGRES_URL"]
+    async fn test_batched_cleanup() {
+        let store = store().await.with_cleanup_batch_size(2);
+        for data in 0..5 {
+            let expired = session(data);
+            expired.set_expiration(Some(SystemTime::now() - Duration::from_secs(1)));
+            store.save(&expired).await.unwrap();
+        }
+
+        // Five rows take three batches of at most two
+        assert_eq!(store.cleanup_expired().await.unwrap(), 5);
+        assert_eq!(store.cleanup_expired().await.unwrap(), 0);
+        drop_table(&store).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires ALTRIA_TEST_POSTGRES_URL"]
+    async fn test_migrations_run_once() {
+        let store = store().await;
+        let session = session(7);
+        store.save(&session).await.unwrap();
+
+        // Reopening runs no migration twice and keeps the data
+        let reopened = PostgresStore::<u64>::with_table_name(store.pool.clone(), &store.table)
+            .await
+            .unwrap();
+        assert_eq!(
+            reopened.load(session.id()).await.unwrap().unwrap().data(),
+            Some(7)
+        );
+
+        let version: i32 = sqlx::query_scalar(AssertSqlSafe(format!(
+            "SELECT version FROM {MIGRATIONS_TABLE} WHERE table_name = $1"
+        )))
+        .bind(&store.table)
+        .fetch_one(&store.pool)
+        .await
+        .unwrap();
+        assert_eq!(version as usize, MIGRATIONS.len());
+        drop_table(&store).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires ALTRIA_TEST_POSTGRES_URL"]
+    async fn test_newer_table_version_is_rejected() {
+        let store = store().await;
+        let newer = MIGRATIONS.len() + 1;
+        sqlx::query(AssertSqlSafe(format!(
+            "UPDATE {MIGRATIONS_TABLE} SET version = $1 WHERE table_name = $2"
+        )))
+        .bind(i32::try_from(newer).unwrap())
+        .bind(&store.table)
+        .execute(&store.pool)
+        .await
+        .unwrap();
+
+        let err = PostgresStore::<u64>::with_table_name(store.pool.clone(), &store.table)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.get_context_value("version"),
+            Some(&ContextValue::from(newer))
+        );
+
+        // The stored version is left untouched
+        let version: i32 = sqlx::query_scalar(AssertSqlSafe(format!(
+            "SELECT version FROM {MIGRATIONS_TABLE} WHERE table_name = $1"
+        )))
+        .bind(&store.table)
+        .fetch_one(&store.pool)
+        .await
+        .unwrap();
+        assert_eq!(version as usize, newer);
+        drop_table(&store).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires ALTRIA_TEST_POSTGRES_URL"]
+    async fn test_concurrent_migrations() {
+        let pool = pool().await;
+        let table = format!("sessions_{}", uuid::Uuid::new_v4().simple());
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let (pool, table) = (pool.clone(), table.clone());
+                tokio::spawn(async move {
+                    PostgresStore::<u64>::with_table_name(pool, &table)
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+        let mut stores = Vec::new();
+        for task in tasks {
+            stores.push(task.await.unwrap());
+        }
+        drop_table(&stores[0]).await;
+    }
+
+    #[test]
+    fn test_invalid_table_name() {
+        for table in [
+            "",
+            "1sessions",
+            "sessions; DROP TABLE users",
+            "my-sessions",
+            "\"x\"",
+            &"s".repeat(MAX_TABLE_NAME_LEN + 1),
+        ] {
+            assert!(!is_valid_table_name(table), "{table}");
+        }
+        assert!(is_valid_table_name("_sessions_v2"));
+        assert!(is_valid_table_name(&"s".repeat(MAX_TABLE_NAME_LEN)));
+    }
+
+    #[test]
+    fn test_has_nul_escape() {
+        let json = |value: &str| serde_json::to_string(value).unwrap();
+        assert!(has_nul_escape(&json("a\0b")));
+        assert!(!has_nul_escape(&json("a\\u0000b")));
+        assert!(has_nul_escape(&json("\\\0")));
+        assert!(!has_nul_escape(&json("plain \u{1}")));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires ALTRIA_TEST_POSTGRES_URL"]
+    async fn test_nul_character_is_rejected() {
+        let store = store().await;
+        let session = SessionBuilder::new()
+            .data(1)
+            .context("note", "a\0b")
+            .build();
+        let err = store.save(&session).await.unwrap_err();
+        assert_eq!(err.get_context("session_id"), Some(session.id()));
+        assert!(store.load(session.id()).await.unwrap().is_none());
+        drop_table(&store).await;
+    }
+
+    #[test]
+    fn test_unix_seconds() {
+        assert!(unix_seconds(UNIX_EPOCH).abs() < f64::EPSILON);
+        assert!((unix_seconds(UNIX_EPOCH + Duration::from_millis(1500)) - 1.5).abs() < 1e-9);
+        assert!((unix_seconds(UNIX_EPOCH - Duration::from_secs(1)) + 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,449 @@
+//! Redis session storage
+
+use super::{Session, SessionStore};
+use crate::error::{Error, Result, ResultExt};
+use redis::aio::ConnectionManager;
+use redis::{Client, IntoConnectionInfo};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+/// Default prefix of session keys
+pub const DEFAULT_KEY_PREFIX: &str = "session:";
+
+/// A [`SessionStore`] persisting sessions in Redis
+///
+/// Available with the `redis` feature. Each session is stored as JSON under
+/// `{prefix}{id}`, with a key TTL matching its
+/// [`expires_at`](Session::expires_at), so Redis evicts expired sessions by
+/// itself and [`cleanup_expired`](SessionStore::cleanup_expired) does nothing.
+/// Sessions without an expiration are stored without a TTL.
+///
+/// Connections are [`ConnectionManager`]s, which multiplex concurrent requests
+/// and reconnect automatically. A pool of several connections, used in turn,
+/// spreads the load of busy services. Clones share the same connections.
+///
+/// Saving a [discarded](Session::discard) or already expired session deletes
+/// it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use altria::web::session::{RedisStore, SessionBuilder, SessionStore};
+/// use std::time::Duration;
+///
+/// # async fn example() -> altria::error::Result<()> {
+/// let store = RedisStore::<u64>::connect_pool("redis://127.0.0.1/", 4)
+///     .await?
+///     .with_prefix("myapp:session:");
+///
+/// let session = SessionBuilder::new()
+///     .data(42)
+///     .expires_in(Duration::from_secs(3600))
+///     .build();
+/// store.save(&session).await?;
+///
+/// let loaded = store.load(session.id()).await?.unwrap();
+/// assert_eq!(loaded.data(), Some(42));
+/// # Ok(())
+/// # }
+/// ```
+pub struct RedisStore<T> {
+    /// Pooled connections, shared between clones
+    connections: Arc<[ConnectionManager]>,
+    /// Index of the next connection to use
+    next: Arc<AtomicUsize>,
+    /// Prefix of session keys
+    prefix: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> RedisStore<T> {
+    /// Use an existing connection
+    #[must_use]
+    pub fn new(connection: ConnectionManager) -> Self {
+        Self {
+            connections: Arc::new([connection]),
+            next: Arc::new(AtomicUsize::new(0)),
+            prefix: DEFAULT_KEY_PREFIX.to_string(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Connect to a Redis server with a single connection
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection information is invalid or the server
+    /// cannot be reached.
+    pub async fn connect(info: impl IntoConnectionInfo) -> Result<Self> {
+        Self::connect_pool(info, 1).await
+    }
+
+    /// Connect to a Redis server with a pool of `size` connections
+    ///
+    /// A size of zero is treated as one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection information is invalid or the server
+    /// cannot be reached.
+    pub async fn connect_pool(info: impl IntoConnectionInfo, size: usize) -> Result<Self> {
+        let client = Client::open(info).context("Invalid Redis connection information")?;
+        let mut connections = Vec::with_capacity(size.max(1));
+        for _ in 0..size.max(1) {
+            let connection = ConnectionManager::new(client.clone())
+                .await
+                .context("Failed to connect to Redis")
+                .with_context_value_lazy("address", || {
+                    client.get_connection_info().addr().to_string()
+                })?;
+            connections.push(connection);
+        }
+        Ok(Self {
+            connections: connections.into(),
+            next: Arc::new(AtomicUsize::new(0)),
+            prefix: DEFAULT_KEY_PREFIX.to_string(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Set the prefix of session keys (builder pattern)
+    ///
+    /// Defaults to [`DEFAULT_KEY_PREFIX`]. Distinct prefixes let several
+    /// applications share a Redis database.
+    #[must_use]
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Get the prefix of session keys
+    #[must_use]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Get the number of pooled connections
+    #[must_use]
+    pub fn pool_size(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Get the Redis key of a session
+    #[must_use]
+    pub fn key(&self, session_id: &str) -> String {
+        format!("{}{session_id}", self.prefix)
+    }
+
+    /// Take the next pooled connection
+    fn connection(&self) -> ConnectionManager {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.connections.len();
+        self.connections[index].clone()
+    }
+}
+
+impl<T> Clone for RedisStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            connections: Arc::clone(&self.connections),
+            next: Arc::clone(&self.next),
+            prefix: self.prefix.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for RedisStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("prefix", &self.prefix)
+            .field("pool_size", &self.connections.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> SessionStore<T> for RedisStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    type Error = Error;
+
+    async fn save(&self, session: &Session<T>) -> Result<()> {
+        let ttl = session
+            .expires_at()
+            .map(|expires_at| ttl_millis(expires_at, SystemTime::now()));
+        if session.is_discarded() || ttl == Some(None) {
+            self.delete(session.id()).await?;
+            session.clear_modified();
+            return Ok(());
+        }
+
+        let json = serde_json::to_string(session).context("Failed to serialize session")?;
+        let key = self.key(session.id());
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(&key).arg(json);
+        if let Some(Some(ttl)) = ttl {
+            cmd.arg("PX").arg(ttl);
+        }
+        cmd.query_async::<()>(&mut self.connection())
+            .await
+            .context("Failed to save session")
+            .with_context_value("key", key)?;
+        session.clear_modified();
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<Session<T>>> {
+        let key = self.key(session_id);
+        let json: Option<String> = redis::cmd("GET")
+            .arg(&key)
+            .query_async(&mut self.connection())
+            .await
+            .context("Failed to load session")
+            .with_context_value("key", key)?;
+        json.map(|json| serde_json::from_str(&json).context("Failed to deserialize session"))
+            .transpose()
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<()> {
+        let key = self.key(session_id);
+        redis::cmd("DEL")
+            .arg(&key)
+            .query_async::<()>(&mut self.connection())
+            .await
+            .context("Failed to delete session")
+            .with_context_value("key", key)
+    }
+
+    /// Redis expires session keys by itself, so there is nothing to clean up
+    async fn cleanup_expired(&self) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+/// Get the TTL in milliseconds until an expiration, or `None` if it has passed
+///
+/// The TTL is rounded up so that a session is never evicted early.
+fn ttl_millis(expires_at: SystemTime, now: SystemTime) -> Option<u64> {
+    let remaining = expires_at.duration_since(now).ok()?;
+    let millis = remaining.as_nanos().div_ceil(1_000_000);
+    (millis > 0).then(|| u64::try_from(millis).unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::session::SessionBuilder;
+    use crate::web::session::tests::{check_store, session};
+    use parking_lot::Mutex;
+    use std::collections::{HashMap, HashSet};
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Keys of the stand-in server, with their optional expiration
+    type Keys = HashMap<Vec<u8>, (Vec<u8>, Option<Instant>)>;
+
+    /// An in-process stand-in for redis-server, speaking just enough RESP
+    #[derive(Clone, Default)]
+    struct FakeRedis {
+        keys: Arc<Mutex<Keys>>,
+        connections: Arc<AtomicUsize>,
+    }
+
+    impl FakeRedis {
+        /// Listen on a local port and return the server with its URL
+        async fn start() -> (Self, String) {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("redis://{}/", listener.local_addr().unwrap());
+            let server = Self::default();
+            let accepting = server.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    accepting.connections.fetch_add(1, Ordering::SeqCst);
+                    tokio::spawn(accepting.clone().serve(stream));
+                }
+            });
+            (server, url)
+        }
+
+        async fn serve(self, stream: TcpStream) {
+            let (reader, mut writer) = stream.into_split();
+            let mut reader = BufReader::new(reader);
+            while let Some(args) = read_command(&mut reader).await {
+                let reply = self.execute(&args);
+                if writer.write_all(&reply).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        fn execute(&self, args: &[Vec<u8>]) -> Vec<u8> {
+            let now = Instant::now();
+            let mut keys = self.keys.lock();
+            keys.retain(|_, (_, expires_at)| expires_at.is_none_or(|at| at > now));
+            match args[0].to_ascii_uppercase().as_slice() {
+                b"PING" => b"+PONG\r\n".to_vec(),
+                b"CLIENT" | b"SELECT" => b"+OK\r\n".to_vec(),
+                b"SET" => {
+                    let expires_at = match args.get(3) {
+                        Some(option) if option.eq_ignore_ascii_case(b"PX") => {
+                            let millis = std::str::from_utf8(&args[4]).unwrap().parse().unwrap();
+                            Some(now + Duration::from_millis(millis))
+                        }
+                        _ => None,
+                    };
+                    keys.insert(args[1].clone(), (args[2].clone(), expires_at));
+                    b"+OK\r\n".to_vec()
+                }
+                b"GET" => keys.get(&args[1]).map_or_else(
+                    || b"$-1\r\n".to_vec(),
+                    |(value, _)| {
+                        let mut reply = format!("${}\r\n", value.len()).into_bytes();
+                        reply.extend_from_slice(value);
+                        reply.extend_from_slice(b"\r\n");
+                        reply
+                    },
+                ),
+                b"DEL" => {
+                    let removed = args[1..].iter().filter(|key| keys.remove(*key).is_some());
+                    format!(":{}\r\n", removed.count()).into_bytes()
+                }
+                _ => b"-ERR unknown command\r\n".to_vec(),
+            }
+        }
+
+        /// Get the remaining TTL of a key, `None` if it has none
+        fn ttl(&self, key: &str) -> Option<Duration> {
+            let keys = self.keys.lock();
+            let (_, expires_at) = keys.get(key.as_bytes()).expect("key not found");
+            expires_at.map(|at| at.saturating_duration_since(Instant::now()))
+        }
+
+        fn contains(&self, key: &str) -> bool {
+            self.keys.lock().contains_key(key.as_bytes())
+        }
+
+        fn session_keys(&self) -> HashSet<Vec<u8>> {
+            self.keys.lock().keys().cloned().collect()
+        }
+    }
+
+    /// Read a RESP array of bulk strings, `None` once the client disconnects
+    async fn read_command<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Option<Vec<Vec<u8>>> {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.ok()?;
+        let count: usize = line.strip_prefix('*')?.trim_end().parse().ok()?;
+        let mut args = Vec::with_capacity(count);
+        for _ in 0..count {
+            line.clear();
+            reader.read_line(&mut line).await.ok()?;
+            let len: usize = line.strip_prefix('$')?.trim_end().parse().ok()?;
+            let mut arg = vec![0; len + 2];
+            reader.read_exact(&mut arg).await.ok()?;
+            arg.truncate(len);
+            args.push(arg);
+        }
+        Some(args)
+    }
+
+    /// Keys of sessions stored with the default prefix
+    fn keys(ids: &HashSet<String>) -> HashSet<Vec<u8>> {
+        ids.iter()
+            .map(|id| format!("{DEFAULT_KEY_PREFIX}{id}").into_bytes())
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_conformance() {
+        let (server, url) = FakeRedis::start().await;
+        let store = RedisStore::<u64>::connect(url.as_str()).await.unwrap();
+        let ids = check_store(store).await;
+        assert_eq!(server.session_keys(), keys(&ids));
+    }
+
+    #[tokio::test]
+    async fn test_prefix() {
+        let (server, url) = FakeRedis::start().await;
+        let store = RedisStore::<u64>::connect(url.as_str())
+            .await
+            .unwrap()
+            .with_prefix("app:");
+        assert_eq!(store.prefix(), "app:");
+        assert_eq!(store.key("abc"), "app:abc");
+
+        let session = session(1);
+        store.save(&session).await.unwrap();
+        assert!(server.contains(&format!("app:{}", session.id())));
+
+        let other = store.clone().with_prefix("other:");
+        assert!(other.load(session.id()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_key_ttl_follows_expiration() {
+        let (server, url) = FakeRedis::start().await;
+        let store = RedisStore::<u64>::connect(url.as_str()).await.unwrap();
+        let session = SessionBuilder::new()
+            .data(1)
+            .expires_in(Duration::from_secs(3600))
+            .build();
+        let key = store.key(session.id());
+
+        store.save(&session).await.unwrap();
+        let ttl = server.ttl(&key).unwrap();
+        assert!(ttl > Duration::from_secs(3590) && ttl <= Duration::from_secs(3600));
+
+        // Removing the expiration persists the key
+        session.set_expiration(None);
+        store.save(&session).await.unwrap();
+        assert_eq!(server.ttl(&key), None);
+
+        // Redis evicts the key once the TTL elapses
+        session.set_expiration(Some(SystemTime::now() + Duration::from_millis(50)));
+        store.save(&session).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(store.load(session.id()).await.unwrap().is_none());
+        assert_eq!(store.cleanup_expired().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_saving_expired_session_deletes_key() {
+        let (server, url) = FakeRedis::start().await;
+        let store = RedisStore::<u64>::connect(url.as_str()).await.unwrap();
+
+        let session = session(1);
+        store.save(&session).await.unwrap();
+        session.set_expiration(Some(SystemTime::now() - Duration::from_secs(1)));
+        store.save(&session).await.unwrap();
+        assert!(!server.contains(&store.key(session.id())));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_connection_pool() {
+        let (server, url) = FakeRedis::start().await;
+        let store = RedisStore::<u64>::connect_pool(url.as_str(), 3)
+            .await
+            .unwrap();
+        assert_eq!(store.pool_size(), 3);
+        assert_eq!(server.connections.load(Ordering::SeqCst), 3);
+
+        // Concurrent use is spread over the pool without new connections
+        let ids = check_store(store).await;
+        assert_eq!(server.connections.load(Ordering::SeqCst), 3);
+        assert_eq!(server.session_keys(), keys(&ids));
+    }
+
+    #[test]
+    fn test_ttl_millis() {
+        let now = SystemTime::now();
+        assert_eq!(ttl_millis(now + Duration::from_secs(2), now), Some(2000));
+        assert_eq!(ttl_millis(now + Duration::from_micros(1), now), Some(1));
+        assert_eq!(ttl_millis(now, now), None);
+        assert_eq!(ttl_millis(now - Duration::from_secs(1), now), None);
+    }
+}
@@ -0,0 +1,413 @@
+//! `SQLite` session storage
+
+use super::{DEFAULT_TABLE_NAME, Session, SessionStore};
+use crate::error::{Error, Result, ResultExt};
+use parking_lot::Mutex;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Table recording the schema version of each sessions table
+const MIGRATIONS_TABLE: &str = "altria_session_migrations";
+
+/// Schema migrations of a sessions table, applied in order
+///
+/// `$table` is replaced with the quoted table name, and `$index` with the
+/// quoted name of the expiration index.
+const MIGRATIONS: &[&str] = &[
+    // 1: Serialized sessions with an indexed expiration in Unix milliseconds
+    "CREATE TABLE $table (
+        id TEXT PRIMARY KEY NOT NULL,
+        session TEXT NOT NULL,
+        expires_at INTEGER
+    );
+    CREATE INDEX $index ON $table (expires_at);",
+];
+
+/// A [`SessionStore`] persisting sessions in a `SQLite` database
+///
+/// Available with the `sqlite` feature. Each session is stored as JSON in a
+/// single row, next to an indexed `expires_at` column holding its expiration
+/// in Unix milliseconds (`NULL` for sessions that never expire). Expired
+/// sessions are never returned by [`load`](SessionStore::load), and
+/// [`cleanup_expired`](SessionStore::cleanup_expired) is a single `DELETE`.
+///
+/// The table is created, or migrated to the current schema, when the store is
+/// opened. Saving a [discarded](Session::discard) session deletes it.
+///
+/// # Examples
+///
+/// ```
+/// use altria::web::session::{SessionBuilder, SessionStore, SqliteStore};
+/// use std::time::Duration;
+///
+/// # async fn example() -> altria::error::Result<()> {
+/// let store = SqliteStore::<u64>::open("sessions.db")?;
+///
+/// let session = SessionBuilder::new()
+///     .data(42)
+///     .expires_in(Duration::from_secs(3600))
+///     .build();
+/// store.save(&session).await?;
+///
+/// let loaded = store.load(session.id()).await?.unwrap();
+/// assert_eq!(loaded.data(), Some(42));
+/// # Ok(())
+/// # }
+/// ```
+pub struct SqliteStore<T> {
+    /// Database connection, shared between clones
+    connection: Arc<Mutex<Connection>>,
+    /// Name of the sessions table
+    table: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> SqliteStore<T> {
+    /// Open or create a database file and use the default `sessions` table
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or migrated.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let connection = Connection::open(path)
+            .context("Failed to open session database")
+            .with_context_value_lazy("path", || path.display().to_string())?;
+        Self::new(connection)
+    }
+
+    /// Open a private in-memory database, mostly useful for tests
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be created.
+    pub fn open_in_memory() -> Result<Self> {
+        let connection = Connection::open_in_memory().context("Failed to open session database")?;
+        Self::new(connection)
+    }
+
+    /// Use an existing connection and the default `sessions` table
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the table cannot be created or migrated.
+    pub fn new(connection: Connection) -> Result<Self> {
+        Self::with_table_name(connection, DEFAULT_TABLE_NAME)
+    }
+
+    /// Use an existing connection and a custom table name
+    ///
+    /// The name must start with an ASCII letter or `_` and contain only ASCII
+    /// letters, digits and `_`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use altria::web::session::SqliteStore;
+    /// use rusqlite::Connection;
+    ///
+    /// let connection = Connection::open_in_memory().unwrap();
+    /// let store = SqliteStore::<u64>::with_table_name(connection, "admin_sessions").unwrap();
+    /// assert_eq!(store.table_name(), "admin_sessions");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid, the table cannot be created or
+    /// migrated, or it was migrated by a newer version of this crate.
+    pub fn with_table_name(mut connection: Connection, table: &str) -> Result<Self> {
+        if !is_valid_table_name(table) {
+            return Err(Error::new("Invalid session table name").with_context_value("table", table));
+        }
+        migrate(&mut connection, table)?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            table: table.to_string(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Get the name of the sessions table
+    #[must_use]
+    pub fn table_name(&self) -> &str {
+        &self.table
+    }
+}
+
+impl<T> Clone for SqliteStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            connection: Arc::clone(&self.connection),
+            table: self.table.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for SqliteStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqliteStore")
+            .field("table", &self.table)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> SessionStore<T> for SqliteStore<T>
+where
+    T: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+{
+    type Error = Error;
+
+    async fn save(&self, session: &Session<T>) -> Result<()> {
+        if session.is_discarded() {
+            self.delete(session.id()).await?;
+            session.clear_modified();
+            return Ok(());
+        }
+
+        let json = serde_json::to_string(session).context("Failed to serialize session")?;
+        let sql = format!(
+            "INSERT INTO \"{}\" (id, session, expires_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (id) DO UPDATE SET session = excluded.session, expires_at = excluded.expires_at",
+            self.table
+        );
+        self.connection
+            .lock()
+            .execute(
+                &sql,
+                params![session.id(), json, session.expires_at().map(unix_millis)],
+            )
+            .context("Failed to save session")
+            .with_context_value("table", self.table.as_str())?;
+        session.clear_modified();
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<Session<T>>> {
+        let sql = format!(
+            "SELECT session FROM \"{}\" WHERE id = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+            self.table
+        );
+        let json: Option<String> = self
+            .connection
+            .lock()
+            .query_row(
+                &sql,
+                params![session_id, unix_millis(SystemTime::now())],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to load session")
+            .with_context_value("table", self.table.as_str())?;
+        json.map(|json| serde_json::from_str(&json).context("Failed to deserialize session"))
+            .transpose()
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<()> {
+        let sql = format!("DELETE FROM \"{}\" WHERE id = ?1", self.table);
+        self.connection
+            .lock()
+            .execute(&sql, params![session_id])
+            .context("Failed to delete session")
+            .with_context_value("table", self.table.as_str())?;
+        Ok(())
+    }
+
+    async fn cleanup_expired(&self) -> Result<usize> {
+        let sql = format!("DELETE FROM \"{}\" WHERE expires_at <= ?1", self.table);
+        self.connection
+            .lock()
+            .execute(&sql, params![unix_millis(SystemTime::now())])
+            .context("Failed to clean up expired sessions")
+            .with_context_value("table", self.table.as_str())
+    }
+}
+
+/// Check that a table name is a plain SQL identifier
+fn is_valid_table_name(table: &str) -> bool {
+    let mut chars = table.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Create the sessions table or bring it up to the current schema
+fn migrate(connection: &mut Connection, table: &str) -> Result<()> {
+    let tx = connection
+        .transaction()
+        .context("Failed to migrate session table")?;
+    tx.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {MIGRATIONS_TABLE} (
+            table_name TEXT PRIMARY KEY NOT NULL,
+            version INTEGER NOT NULL
+        );"
+    ))
+    .context("Failed to create session migrations table")?;
+
+    let version: usize = tx
+        .query_row(
+            &format!("SELECT version FROM {MIGRATIONS_TABLE} WHERE table_name = ?1"),
+            params![table],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("Failed to read session table version")?
+        .unwrap_or(0);
+    if version > MIGRATIONS.len() {
+        // Recording our version would downgrade the table under newer code
+        return Err(Error::new("Session table was migrated by a newer version")
+            .with_context_value("table", table)
+            .with_context_value("version", version));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let sql = migration
+            .replace("$table", &format!("\"{table}\""))
+            .replace("$index", &format!("\"{table}_expires_at_idx\""));
+        tx.execute_batch(&sql)
+            .context("Failed to migrate session table")
+            .with_context_value("table", table)
+            .with_context_value("version", index + 1)?;
+    }
+    tx.execute(
+        &format!(
+            "INSERT INTO {MIGRATIONS_TABLE} (table_name, version) VALUES (?1, ?2)
+             ON CONFLICT (table_name) DO UPDATE SET version = excluded.version"
+        ),
+        params![table, MIGRATIONS.len()],
+    )
+    .context("Failed to record session table version")?;
+    tx.commit().context("Failed to migrate session table")
+}
+
+/// Convert a time to Unix milliseconds, saturating outside the `i64` range
+fn unix_millis(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => i64::try_from(elapsed.as_millis()).unwrap_or(i64::MAX),
+        Err(before) => i64::try_from(before.duration().as_millis()).map_or(i64::MIN, |ms| -ms),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ContextValue;
+    use crate::web::session::tests::{check_store, session};
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_conformance() {
+        let store = SqliteStore::<u64>::open_in_memory().unwrap();
+        let ids = check_store(store.clone()).await;
+        let connection = store.connection.lock();
+        let stored: HashSet<String> = connection
+            .prepare("SELECT id FROM sessions")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(stored, ids);
+    }
+
+    #[tokio::test]
+    async fn test_table_name_and_migrations() {
+        let dir = std::env::temp_dir().join(format!("altria-sqlite-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("sessions.db");
+
+        let session = session(7);
+        {
+            let connection = Connection::open(&path).unwrap();
+            let store = SqliteStore::<u64>::with_table_name(connection, "app_sessions").unwrap();
+            store.save(&session).await.unwrap();
+        }
+
+        // Reopening runs no migration twice and keeps the data
+        let connection = Connection::open(&path).unwrap();
+        let store = SqliteStore::<u64>::with_table_name(connection, "app_sessions").unwrap();
+        assert_eq!(
+            store.load(session.id()).await.unwrap().unwrap().data(),
+            Some(7)
+        );
+
+        // Other tables in the same database are independent
+        let other = SqliteStore::<u64>::open(&path).unwrap();
+        assert!(other.load(session.id()).await.unwrap().is_none());
+
+        let version: usize = store
+            .connection
+            .lock()
+            .query_row(
+                &format!(
+                    "SELECT version FROM {MIGRATIONS_TABLE} WHERE table_name = 'app_sessions'"
+                ),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+
+        drop((store, other));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_newer_table_version_is_rejected() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        migrate(&mut connection, "sessions").unwrap();
+        let newer = MIGRATIONS.len() + 1;
+        connection
+            .execute(
+                &format!(
+                    "UPDATE {MIGRATIONS_TABLE} SET version = ?1 WHERE table_name = 'sessions'"
+                ),
+                params![newer],
+            )
+            .unwrap();
+
+        let err = migrate(&mut connection, "sessions").unwrap_err();
+        assert_eq!(
+            err.get_context_value("version"),
+            Some(&ContextValue::from(newer))
+        );
+        let version: usize = connection
+            .query_row(
+                &format!("SELECT version FROM {MIGRATIONS_TABLE} WHERE table_name = 'sessions'"),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(version, newer);
+    }
+
+    #[test]
+    fn test_invalid_table_name() {
+        for table in [
+            "",
+            "1sessions",
+            "sessions; DROP TABLE users",
+            "my-sessions",
+            "\"x\"",
+        ] {
+            let connection = Connection::open_in_memory().unwrap();
+            let err = SqliteStore::<u64>::with_table_name(connection, table).unwrap_err();
+            assert_eq!(err.get_context("table"), Some(table));
+        }
+        assert!(is_valid_table_name("_sessions_v2"));
+    }
+
+    #[test]
+    fn test_unix_millis() {
+        assert_eq!(unix_millis(UNIX_EPOCH), 0);
+        assert_eq!(unix_millis(UNIX_EPOCH + Duration::from_millis(1500)), 1500);
+        assert_eq!(unix_millis(UNIX_EPOCH - Duration::from_secs(1)), -1000);
+    }
+}